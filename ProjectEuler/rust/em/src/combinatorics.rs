@@ -0,0 +1,408 @@
+//! Exact and modular tables of combinatorial numbers
+//!
+//! Each family is provided as a single value and as a whole row (or table), the exact
+//! versions return big integers and the `_mod` versions return the values modulo m.
+//! Binomial coefficients modulo m for large n are available in `crate::modcomb`.
+
+use std::collections::HashMap;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use crate::traits::{ModInt, ModUnary};
+use crate::modcomb::{BinomialMod, FactorialTable};
+use crate::prime::PrimeBuffer;
+
+/// The arithmetic used to fill the tables, either exact or modulo m
+pub(crate) trait Ring {
+    type Item: Clone;
+    fn num(&self, x: u64) -> Self::Item;
+    fn add(&self, a: &Self::Item, b: &Self::Item) -> Self::Item;
+    /// a - b, it's guaranteed that a >= b for exact numbers
+    fn sub(&self, a: &Self::Item, b: &Self::Item) -> Self::Item;
+    fn mul(&self, a: &Self::Item, b: &Self::Item) -> Self::Item;
+}
+
+pub(crate) struct Exact;
+
+impl Ring for Exact {
+    type Item = BigUint;
+    fn num(&self, x: u64) -> BigUint { BigUint::from(x) }
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint { a + b }
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint { a - b }
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint { a * b }
+}
+
+pub(crate) struct Modular(pub u64);
+
+impl Ring for Modular {
+    type Item = u64;
+    fn num(&self, x: u64) -> u64 { x % self.0 }
+    fn add(&self, a: &u64, b: &u64) -> u64 { a.add_mod(b, &self.0) }
+    fn sub(&self, a: &u64, b: &u64) -> u64 { a.sub_mod(b, &self.0) }
+    fn mul(&self, a: &u64, b: &u64) -> u64 { a.mul_mod(b, &self.0) }
+}
+
+/// Function building the row n of a triangle from the row n-1
+type NextRow<R> = fn(&R, &[<R as Ring>::Item], usize) -> Vec<<R as Ring>::Item>;
+
+/// Generate the rows 0..=n of a triangle, each row is built from the previous one
+fn rows<R: Ring>(r: &R, n: usize, next: NextRow<R>) -> Vec<Vec<R::Item>> {
+    let mut table = vec![vec![r.num(1)]];
+    for i in 1..=n {
+        let row = next(r, &table[i - 1], i);
+        table.push(row);
+    }
+    table
+}
+
+/// Generate only the row n of a triangle
+pub(crate) fn last_row<R: Ring>(r: &R, n: usize, next: NextRow<R>) -> Vec<R::Item> {
+    (1..=n).fold(vec![r.num(1)], |row, i| next(r, &row, i))
+}
+
+/// Get the element at position k of a row, zero if out of range
+fn entry<R: Ring>(r: &R, row: Vec<R::Item>, k: usize) -> R::Item {
+    row.into_iter().nth(k).unwrap_or_else(|| r.num(0))
+}
+
+// C(n, k) = C(n-1, k-1) + C(n-1, k)
+fn binomial_next<R: Ring>(r: &R, prev: &[R::Item], n: usize) -> Vec<R::Item> {
+    (0..=n).map(|k| match k {
+        0 => prev[0].clone(),
+        _ if k == n => prev[n - 1].clone(),
+        _ => r.add(&prev[k - 1], &prev[k])
+    }).collect()
+}
+
+// s(n, k) = (n-1) * s(n-1, k) + s(n-1, k-1)
+fn stirling1_next<R: Ring>(r: &R, prev: &[R::Item], n: usize) -> Vec<R::Item> {
+    let factor = r.num(n as u64 - 1);
+    (0..=n).map(|k| {
+        let stay = if k < n { r.mul(&factor, &prev[k]) } else { r.num(0) };
+        if k > 0 { r.add(&stay, &prev[k - 1]) } else { stay }
+    }).collect()
+}
+
+// S(n, k) = k * S(n-1, k) + S(n-1, k-1)
+pub(crate) fn stirling2_next<R: Ring>(r: &R, prev: &[R::Item], n: usize) -> Vec<R::Item> {
+    (0..=n).map(|k| {
+        let stay = if k < n { r.mul(&r.num(k as u64), &prev[k]) } else { r.num(0) };
+        if k > 0 { r.add(&stay, &prev[k - 1]) } else { stay }
+    }).collect()
+}
+
+// A(n, k) = (k+1) * A(n-1, k) + (n-k) * A(n-1, k-1), the row n has n entries (one for n = 0)
+fn eulerian_next<R: Ring>(r: &R, prev: &[R::Item], n: usize) -> Vec<R::Item> {
+    let zero = r.num(0);
+    (0..n).map(|k| {
+        let stay = prev.get(k).map_or(zero.clone(), |a| r.mul(&r.num(k as u64 + 1), a));
+        match k {
+            0 => stay,
+            _ => r.add(&stay, &r.mul(&r.num((n - k) as u64), &prev[k - 1]))
+        }
+    }).collect()
+}
+
+// N(n, k) = C(n-1, k-1)^2 - C(n-1, k-2) * C(n-1, k), with k in [0, n]
+fn narayana_row<R: Ring>(r: &R, binomials: &[R::Item], n: usize) -> Vec<R::Item> {
+    if n == 0 {
+        return vec![r.num(1)];
+    }
+    let zero = r.num(0);
+    let c = |k: usize| if k < n { binomials[k].clone() } else { zero.clone() }; // C(n-1, k)
+    (0..=n).map(|k| match k {
+        0 => zero.clone(),
+        1 => r.mul(&c(0), &c(0)),
+        _ => r.sub(&r.mul(&c(k - 1), &c(k - 1)), &r.mul(&c(k - 2), &c(k)))
+    }).collect()
+}
+
+fn narayana_rows<R: Ring>(r: &R, n: usize) -> Vec<Vec<R::Item>> {
+    let binomials = rows(r, n.saturating_sub(1), binomial_next);
+    (0..=n).map(|i| narayana_row(r, &binomials[i.saturating_sub(1)], i)).collect()
+}
+
+// the first numbers of the rows in Bell's triangle
+fn bell_numbers_in<R: Ring>(r: &R, n: usize) -> Vec<R::Item> {
+    let mut result = vec![r.num(1)];
+    let mut row = vec![r.num(1)];
+    for _ in 0..n {
+        let mut next = vec![row.last().unwrap().clone()];
+        for x in &row {
+            let value = r.add(next.last().unwrap(), x);
+            next.push(value);
+        }
+        result.push(next[0].clone());
+        row = next;
+    }
+    result
+}
+
+// C_(n+1) = sum(C_i * C_(n-i))
+fn catalan_numbers_in<R: Ring>(r: &R, n: usize) -> Vec<R::Item> {
+    let mut result = vec![r.num(1)];
+    for i in 0..n {
+        let value = (0..=i).fold(r.num(0), |acc, j| r.add(&acc, &r.mul(&result[j], &result[i - j])));
+        result.push(value);
+    }
+    result
+}
+
+// D(n) = (n-1) * (D(n-1) + D(n-2))
+fn derangements_in<R: Ring>(r: &R, n: usize) -> Vec<R::Item> {
+    let mut result = vec![r.num(1), r.num(0)];
+    for i in 2..=n {
+        let value = r.mul(&r.num(i as u64 - 1), &r.add(&result[i - 1], &result[i - 2]));
+        result.push(value);
+    }
+    result.truncate(n + 1);
+    result
+}
+
+/// Returns the binomial coefficient C(n, k)
+pub fn binomial(n: u64, k: u64) -> BigUint {
+    if k > n {
+        return BigUint::zero();
+    }
+    let k = k.min(n - k);
+    (0..k).fold(BigUint::one(), |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Returns C(n, k) for k = 0..=n
+pub fn binomial_row(n: usize) -> Vec<BigUint> { last_row(&Exact, n, binomial_next) }
+
+/// Returns C(n, k) mod m for k = 0..=n
+pub fn binomial_row_mod(n: usize, m: u64) -> Vec<u64> {
+    if m == 1 {
+        return vec![0; n + 1];
+    }
+
+    // C(n, k) = C(n, k-1) * (n-k+1) / k, where the prime factors of m are counted
+    // separately so that the rest of the divisor is invertible
+    let primes: Vec<u64> = PrimeBuffer::new().factors(m).into_keys().collect();
+    let mut exponents = vec![0u64; primes.len()];
+    let mut unit = 1;
+    let mut row = Vec::with_capacity(n + 1);
+    row.push(1);
+    for k in 1..=n {
+        let (mut num, mut den) = ((n - k + 1) as u64, k as u64);
+        for (&p, e) in primes.iter().zip(exponents.iter_mut()) {
+            while num.is_multiple_of(p) {
+                num /= p;
+                *e += 1;
+            }
+            while den.is_multiple_of(p) {
+                den /= p;
+                *e -= 1;
+            }
+        }
+        unit = unit.mul_mod(num, &m).mul_mod(den.inv_mod(&m).unwrap(), &m);
+        row.push(primes.iter().zip(&exponents).fold(unit, |acc, (&p, &e)| acc.mul_mod(p.pow_mod(e, &m), &m)));
+    }
+    row
+}
+
+/// Returns the unsigned Stirling number of the first kind, which counts the permutations
+/// of n elements with k cycles
+pub fn stirling1(n: usize, k: usize) -> BigUint { entry(&Exact, last_row(&Exact, n, stirling1_next), k) }
+
+/// Returns the unsigned Stirling number of the first kind modulo m
+pub fn stirling1_mod(n: usize, k: usize, m: u64) -> u64 { entry(&Modular(m), last_row(&Modular(m), n, stirling1_next), k) }
+
+/// Returns the table of unsigned Stirling numbers of the first kind, the row i contains s(i, k) for k = 0..=i
+pub fn stirling1_table(n: usize) -> Vec<Vec<BigUint>> { rows(&Exact, n, stirling1_next) }
+
+/// Returns the table of unsigned Stirling numbers of the first kind modulo m
+pub fn stirling1_table_mod(n: usize, m: u64) -> Vec<Vec<u64>> { rows(&Modular(m), n, stirling1_next) }
+
+/// Returns the Stirling number of the second kind, which counts the partitions of n
+/// elements into k non-empty subsets
+pub fn stirling2(n: usize, k: usize) -> BigUint { entry(&Exact, last_row(&Exact, n, stirling2_next), k) }
+
+/// Returns the Stirling number of the second kind modulo m
+pub fn stirling2_mod(n: usize, k: usize, m: u64) -> u64 { entry(&Modular(m), last_row(&Modular(m), n, stirling2_next), k) }
+
+/// Returns the table of Stirling numbers of the second kind, the row i contains S(i, k) for k = 0..=i
+pub fn stirling2_table(n: usize) -> Vec<Vec<BigUint>> { rows(&Exact, n, stirling2_next) }
+
+/// Returns the table of Stirling numbers of the second kind modulo m
+pub fn stirling2_table_mod(n: usize, m: u64) -> Vec<Vec<u64>> { rows(&Modular(m), n, stirling2_next) }
+
+/// Returns the n-th Bell number, which counts the partitions of n elements
+pub fn bell(n: usize) -> BigUint { bell_numbers_in(&Exact, n).pop().unwrap() }
+
+/// Returns the n-th Bell number modulo m
+pub fn bell_mod(n: usize, m: u64) -> u64 { bell_numbers_in(&Modular(m), n).pop().unwrap() }
+
+/// Returns the Bell numbers B_0, ..., B_n
+pub fn bell_numbers(n: usize) -> Vec<BigUint> { bell_numbers_in(&Exact, n) }
+
+/// Returns the Bell numbers B_0, ..., B_n modulo m
+pub fn bell_numbers_mod(n: usize, m: u64) -> Vec<u64> { bell_numbers_in(&Modular(m), n) }
+
+/// Returns the n-th Catalan number C(2n, n) / (n + 1)
+pub fn catalan(n: usize) -> BigUint { binomial(2 * n as u64, n as u64) / (n + 1) }
+
+/// Returns the n-th Catalan number modulo m
+pub fn catalan_mod(n: usize, m: u64) -> u64 {
+    // C(2n, n) - C(2n, n+1) avoids the division
+    let mut pb = PrimeBuffer::new();
+    if m > 2 * n as u64 && pb.is_prime(m) {
+        let table = FactorialTable::new(2 * n, m);
+        return table.binomial(2 * n, n).sub_mod(table.binomial(2 * n, n + 1), &m);
+    }
+    let factors = if m == 1 { HashMap::new() } else { pb.factors(m) };
+    let binomial = BinomialMod::new(&factors);
+    let (n, n2) = (n as u64, 2 * n as u64);
+    binomial.binomial(n2, n).sub_mod(binomial.binomial(n2, n + 1), &m)
+}
+
+/// Returns the Catalan numbers C_0, ..., C_n
+pub fn catalan_numbers(n: usize) -> Vec<BigUint> { catalan_numbers_in(&Exact, n) }
+
+/// Returns the Catalan numbers C_0, ..., C_n modulo m
+pub fn catalan_numbers_mod(n: usize, m: u64) -> Vec<u64> { catalan_numbers_in(&Modular(m), n) }
+
+/// Returns the Narayana number N(n, k), which counts the Dyck paths of length 2n with k peaks
+pub fn narayana(n: usize, k: usize) -> BigUint {
+    entry(&Exact, narayana_row(&Exact, &binomial_row(n.saturating_sub(1)), n), k)
+}
+
+/// Returns the Narayana number N(n, k) modulo m
+pub fn narayana_mod(n: usize, k: usize, m: u64) -> u64 {
+    entry(&Modular(m), narayana_row(&Modular(m), &binomial_row_mod(n.saturating_sub(1), m), n), k)
+}
+
+/// Returns the table of Narayana numbers, the row i contains N(i, k) for k = 0..=i
+pub fn narayana_table(n: usize) -> Vec<Vec<BigUint>> { narayana_rows(&Exact, n) }
+
+/// Returns the table of Narayana numbers modulo m
+pub fn narayana_table_mod(n: usize, m: u64) -> Vec<Vec<u64>> { narayana_rows(&Modular(m), n) }
+
+/// Returns the Eulerian number A(n, k), which counts the permutations of n elements with k ascents
+pub fn eulerian(n: usize, k: usize) -> BigUint { entry(&Exact, last_row(&Exact, n, eulerian_next), k) }
+
+/// Returns the Eulerian number A(n, k) modulo m
+pub fn eulerian_mod(n: usize, k: usize, m: u64) -> u64 { entry(&Modular(m), last_row(&Modular(m), n, eulerian_next), k) }
+
+/// Returns the table of Eulerian numbers, the row i contains A(i, k) for k = 0..i (and A(0, 0) for i = 0)
+pub fn eulerian_table(n: usize) -> Vec<Vec<BigUint>> { rows(&Exact, n, eulerian_next) }
+
+/// Returns the table of Eulerian numbers modulo m
+pub fn eulerian_table_mod(n: usize, m: u64) -> Vec<Vec<u64>> { rows(&Modular(m), n, eulerian_next) }
+
+/// Returns the number of derangements of n elements, i.e. permutations without fixed points
+pub fn derangement(n: usize) -> BigUint { derangements_in(&Exact, n).pop().unwrap() }
+
+/// Returns the number of derangements of n elements modulo m
+pub fn derangement_mod(n: usize, m: u64) -> u64 { derangements_in(&Modular(m), n).pop().unwrap() }
+
+/// Returns the numbers of derangements D_0, ..., D_n
+pub fn derangements(n: usize) -> Vec<BigUint> { derangements_in(&Exact, n) }
+
+/// Returns the numbers of derangements D_0, ..., D_n modulo m
+pub fn derangements_mod(n: usize, m: u64) -> Vec<u64> { derangements_in(&Modular(m), n) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+
+    fn to_u64(v: &[BigUint]) -> Vec<u64> {
+        v.iter().map(|x| x.to_u64().unwrap()).collect()
+    }
+
+    fn reduce(v: &[BigUint], m: u64) -> Vec<u64> {
+        v.iter().map(|x| (x % m).to_u64().unwrap()).collect()
+    }
+
+    #[test]
+    fn small_values_test() {
+        assert_eq!(to_u64(&binomial_row(5)), [1, 5, 10, 10, 5, 1]);
+        assert_eq!(to_u64(&stirling1_table(4)[4]), [0, 6, 11, 6, 1]);
+        assert_eq!(to_u64(&stirling2_table(4)[4]), [0, 1, 7, 6, 1]);
+        assert_eq!(to_u64(&bell_numbers(7)), [1, 1, 2, 5, 15, 52, 203, 877]);
+        assert_eq!(to_u64(&catalan_numbers(7)), [1, 1, 2, 5, 14, 42, 132, 429]);
+        assert_eq!(to_u64(&narayana_table(4)[4]), [0, 1, 6, 6, 1]);
+        assert_eq!(to_u64(&eulerian_table(4)[4]), [1, 11, 11, 1]);
+        assert_eq!(to_u64(&eulerian_table(1)[0]), [1]);
+        assert_eq!(to_u64(&derangements(6)), [1, 0, 1, 2, 9, 44, 265]);
+        assert_eq!(to_u64(&derangements(0)), [1]);
+
+        assert_eq!(binomial(100, 50).to_string(), "100891344545564193334812497256");
+        assert_eq!(catalan(50).to_string(), "1978261657756160653623774456");
+        assert_eq!(bell(30).to_string(), "846749014511809332450147");
+        assert_eq!(stirling2(5, 7), BigUint::zero());
+        assert_eq!(narayana(0, 0), BigUint::one());
+    }
+
+    #[test]
+    fn identity_test() {
+        let n = 30;
+        let (s1, s2) = (stirling1_table(n), stirling2_table(n));
+        let (a, t) = (eulerian_table(n), narayana_table(n));
+        let (bells, catalans) = (bell_numbers(n), catalan_numbers(n));
+        let mut factorial = BigUint::one();
+        for i in 0..=n {
+            if i > 0 {
+                factorial *= i;
+            }
+            assert_eq!(s1[i].iter().sum::<BigUint>(), factorial);
+            assert_eq!(a[i].iter().sum::<BigUint>(), factorial);
+            assert_eq!(s2[i].iter().sum::<BigUint>(), bells[i]);
+            assert_eq!(t[i].iter().sum::<BigUint>(), catalans[i]);
+            assert_eq!(catalan(i), catalans[i]);
+            assert_eq!(bell(i), bells[i]);
+            assert_eq!(binomial_row(i), (0..=i as u64).map(|k| binomial(i as u64, k)).collect::<Vec<_>>());
+            for k in 0..=i {
+                assert_eq!(stirling1(i, k), s1[i][k]);
+                assert_eq!(stirling2(i, k), s2[i][k]);
+                assert_eq!(narayana(i, k), t[i][k]);
+            }
+        }
+    }
+
+    #[test]
+    fn modular_test() {
+        let n = 40;
+        for m in [1u64, 2, 1000, 1000000007] {
+            assert_eq!(binomial_row_mod(n, m), reduce(&binomial_row(n), m));
+            assert_eq!(bell_numbers_mod(n, m), reduce(&bell_numbers(n), m));
+            assert_eq!(catalan_numbers_mod(n, m), reduce(&catalan_numbers(n), m));
+            assert_eq!(derangements_mod(n, m), reduce(&derangements(n), m));
+            assert_eq!(bell_mod(n, m), (bell(n) % m).to_u64().unwrap());
+            assert_eq!(catalan_mod(n, m), (catalan(n) % m).to_u64().unwrap());
+            assert_eq!(derangement_mod(n, m), (derangement(n) % m).to_u64().unwrap());
+        }
+        for m in [3u64, 37, 1024, 1000000, 18446744073709551557] {
+            assert_eq!(binomial_row_mod(n, m), reduce(&binomial_row(n), m));
+            assert_eq!(catalan_mod(n, m), (catalan(n) % m).to_u64().unwrap());
+
+            let tables = [
+                (stirling1_table_mod(n, m), stirling1_table(n)),
+                (stirling2_table_mod(n, m), stirling2_table(n)),
+                (narayana_table_mod(n, m), narayana_table(n)),
+                (eulerian_table_mod(n, m), eulerian_table(n))
+            ];
+            for (modular, exact) in &tables {
+                for (row_mod, row) in modular.iter().zip(exact) {
+                    assert_eq!(row_mod, &reduce(row, m));
+                }
+            }
+            for k in 0..=n {
+                assert_eq!(stirling1_mod(n, k, m), tables[0].0[n][k]);
+                assert_eq!(stirling2_mod(n, k, m), tables[1].0[n][k]);
+                assert_eq!(narayana_mod(n, k, m), tables[2].0[n][k]);
+                assert_eq!(eulerian_mod(n, k, m), tables[3].0[n].get(k).cloned().unwrap_or(0));
+            }
+        }
+    }
+
+    #[test]
+    fn large_modular_test() {
+        assert_eq!(catalan_mod(1000000, 1000000007), 70646122);
+        assert_eq!(catalan_mod(1000000, 1000000000), 72208000);
+        assert_eq!(catalan_mod(1000000, 37), 0);
+        let row = binomial_row_mod(100000, 1000000000);
+        assert_eq!([row[1], row[2], row[12345], row[50000], row[99999]], [100000, 999950000, 870400000, 891416640, 100000]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Arithmetic functions as arrays, under the Dirichlet convolution
+//!
+//! An `ArithmeticFunction` stores f(1), ..., f(N) for a limit N. The Dirichlet product
+//! (f * g)(n) = sum(f(d) * g(n / d) for d | n) takes O(N log N) operations, and the Möbius
+//! transform (f * 1) and its inverse (f * μ) take O(N log log N) with the primes from
+//! `PrimeBuffer`. Sieve outputs such as `PrimeBuffer::mobius` can be converted by
+//! `from_values`.
+
+use std::ops::{Add, Sub, Mul, Div, Index};
+use num_traits::{Zero, One};
+use crate::prime::PrimeBuffer;
+
+/// The values f(1), ..., f(N) of an arithmetic function. The value at index 0 is kept as
+/// zero, so that f(n) is at index n.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArithmeticFunction<T> {
+    values: Vec<T>
+}
+
+impl<T: Clone + Zero> ArithmeticFunction<T> {
+    /// Create from the values indexed by n, the value at index 0 is ignored
+    pub fn new(mut values: Vec<T>) -> Self {
+        if values.is_empty() {
+            values.push(T::zero());
+        }
+        values[0] = T::zero();
+        ArithmeticFunction { values }
+    }
+
+    /// Create by converting the values indexed by n (e.g. the output of `PrimeBuffer::mobius`)
+    pub fn from_values<U: Clone + Into<T>>(values: &[U]) -> Self {
+        Self::new(values.iter().map(|x| x.clone().into()).collect())
+    }
+
+    /// Create from f(n) for n in 1..=limit
+    pub fn from_fn<F: FnMut(u64) -> T>(limit: u64, mut f: F) -> Self {
+        Self::new((0..=limit).map(|n| if n == 0 { T::zero() } else { f(n) }).collect())
+    }
+
+    /// Returns the limit N
+    #[inline]
+    pub fn limit(&self) -> u64 { self.values.len() as u64 - 1 }
+
+    /// Returns the values indexed by n, with zero at index 0
+    #[inline]
+    pub fn values(&self) -> &[T] { &self.values }
+
+    /// Returns the prefix sums f(1) + ... + f(n) indexed by n
+    pub fn prefix_sums(&self) -> Vec<T> {
+        self.values.iter().scan(T::zero(), |acc, x| {
+            *acc = acc.clone() + x.clone();
+            Some(acc.clone())
+        }).collect()
+    }
+
+    /// Apply a function to each value
+    pub fn map<U: Clone + Zero, F: FnMut(&T) -> U>(&self, f: F) -> ArithmeticFunction<U> {
+        ArithmeticFunction::new(self.values.iter().map(f).collect())
+    }
+}
+
+impl<T: Clone + Zero + One + Sub<Output = T>> ArithmeticFunction<T> {
+    /// The identity of the Dirichlet product, ε(1) = 1 and ε(n) = 0 otherwise
+    pub fn unit(limit: u64) -> Self {
+        Self::from_fn(limit, |n| if n == 1 { T::one() } else { T::zero() })
+    }
+
+    /// The constant function 1
+    pub fn one(limit: u64) -> Self { Self::from_fn(limit, |_| T::one()) }
+
+    /// The identity function id(n) = n
+    pub fn identity(limit: u64) -> Self {
+        let mut n = T::zero();
+        Self::from_fn(limit, |_| {
+            n = n.clone() + T::one();
+            n.clone()
+        })
+    }
+
+    /// Returns the Dirichlet product f * g up to the smaller limit
+    pub fn dirichlet(&self, rhs: &Self) -> Self {
+        let limit = self.limit().min(rhs.limit()) as usize;
+        let mut values = vec![T::zero(); limit + 1];
+        for d in 1..=limit {
+            if self.values[d].is_zero() {
+                continue;
+            }
+            for (k, n) in (d..=limit).step_by(d).enumerate() {
+                values[n] = values[n].clone() + self.values[d].clone() * rhs.values[k + 1].clone();
+            }
+        }
+        Self::new(values)
+    }
+
+    /// Returns the pointwise product f(n) * g(n)
+    pub fn pointwise(&self, rhs: &Self) -> Self {
+        Self::new(self.values.iter().zip(&rhs.values).map(|(a, b)| a.clone() * b.clone()).collect())
+    }
+
+    /// Returns c * f(n)
+    pub fn scale(&self, c: &T) -> Self {
+        self.map(|a| a.clone() * c.clone())
+    }
+
+    /// Returns the Möbius transform g(n) = sum(f(d) for d | n), i.e. f * 1. The divisor
+    /// sums are accumulated one prime at a time.
+    pub fn mobius_transform(&self, pb: &mut PrimeBuffer) -> Self {
+        let mut values = self.values.clone();
+        let limit = values.len() - 1;
+        for &p in pb.primes(limit as u64 + 1) {
+            let p = p as usize;
+            for i in 1..=limit / p {
+                values[i * p] = values[i * p].clone() + values[i].clone();
+            }
+        }
+        Self::new(values)
+    }
+
+    /// Returns the inverse Möbius transform f(n) = sum(μ(n / d) * g(d) for d | n), i.e. g * μ
+    pub fn inverse_mobius_transform(&self, pb: &mut PrimeBuffer) -> Self {
+        let mut values = self.values.clone();
+        let limit = values.len() - 1;
+        for &p in pb.primes(limit as u64 + 1) {
+            let p = p as usize;
+            for i in (1..=limit / p).rev() {
+                values[i * p] = values[i * p].clone() - values[i].clone();
+            }
+        }
+        Self::new(values)
+    }
+}
+
+impl<T: Clone + Zero + One + Sub<Output = T> + Div<Output = T>> ArithmeticFunction<T> {
+    /// Returns the Dirichlet inverse g with f * g = ε, f(1) has to be invertible (so it
+    /// must be ±1 for the integer types)
+    pub fn dirichlet_inverse(&self) -> Self {
+        let limit = self.values.len() - 1;
+        assert!(limit == 0 || !self.values[1].is_zero());
+        let mut values = vec![T::zero(); limit + 1];
+        if limit == 0 {
+            return Self::new(values);
+        }
+
+        // g(n) = -sum(f(n / d) * g(d) for d | n, d < n) / f(1)
+        let inv = T::one() / self.values[1].clone();
+        values[1] = inv.clone();
+        let mut partial = vec![T::zero(); limit + 1]; // the sums over d < n
+        for d in 1..=limit {
+            if d > 1 {
+                values[d] = T::zero() - partial[d].clone() * inv.clone();
+            }
+            if values[d].is_zero() {
+                continue;
+            }
+            for (k, n) in (2 * d..=limit).step_by(d).enumerate() {
+                partial[n] = partial[n].clone() + self.values[k + 2].clone() * values[d].clone();
+            }
+        }
+        Self::new(values)
+    }
+}
+
+impl<T> Index<usize> for ArithmeticFunction<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, n: usize) -> &T { &self.values[n] }
+}
+
+impl<T: Clone + Zero> Add for &ArithmeticFunction<T> {
+    type Output = ArithmeticFunction<T>;
+
+    fn add(self, rhs: &ArithmeticFunction<T>) -> ArithmeticFunction<T> {
+        ArithmeticFunction::new(self.values.iter().zip(&rhs.values).map(|(a, b)| a.clone() + b.clone()).collect())
+    }
+}
+
+impl<T: Clone + Zero + Sub<Output = T>> Sub for &ArithmeticFunction<T> {
+    type Output = ArithmeticFunction<T>;
+
+    fn sub(self, rhs: &ArithmeticFunction<T>) -> ArithmeticFunction<T> {
+        ArithmeticFunction::new(self.values.iter().zip(&rhs.values).map(|(a, b)| a.clone() - b.clone()).collect())
+    }
+}
+
+impl<T: Clone + Zero + One + Sub<Output = T>> Mul for &ArithmeticFunction<T> {
+    type Output = ArithmeticFunction<T>;
+
+    /// The Dirichlet product
+    #[inline]
+    fn mul(self, rhs: &ArithmeticFunction<T>) -> ArithmeticFunction<T> { self.dirichlet(rhs) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_integer::Integer;
+    use crate::modint::Mod;
+
+    #[test]
+    fn dirichlet_test() {
+        let n = 1000;
+        let mut pb = PrimeBuffer::new();
+        let mu = ArithmeticFunction::<i64>::from_values(&pb.mobius(n + 1));
+        let one = ArithmeticFunction::one(n);
+        let id = ArithmeticFunction::identity(n);
+        let phi = ArithmeticFunction::from_fn(n, |k| (1..=k).filter(|i| i.gcd(&k) == 1).count() as i64);
+
+        assert_eq!(&mu * &one, ArithmeticFunction::unit(n));
+        assert_eq!(one.dirichlet_inverse(), mu);
+        assert_eq!(mu.dirichlet_inverse(), one);
+        assert_eq!(&mu * &id, phi);
+        assert_eq!(phi.mobius_transform(&mut pb), id);
+        assert_eq!(id.inverse_mobius_transform(&mut pb), phi);
+        assert_eq!(one.mobius_transform(&mut pb), &one * &one);
+        assert_eq!(phi.prefix_sums()[n as usize], 304192);
+
+        // σ(n) = (id * 1)(n) and the pointwise operations
+        let sigma = &id * &one;
+        assert_eq!(sigma[12], 28);
+        assert_eq!((&sigma - &id)[7], 1);
+        assert_eq!((&sigma + &id.scale(&2)).pointwise(&one)[6], 24);
+        assert_eq!(ArithmeticFunction::<i64>::new(Vec::new()).limit(), 0);
+
+        // big integers and modular values, where f(1) doesn't need to be one
+        let mu_big = ArithmeticFunction::<BigInt>::from_values(&pb.mobius(101));
+        assert_eq!(mu_big.mobius_transform(&mut pb), ArithmeticFunction::unit(100));
+        type M = Mod<1000000007>;
+        let f = ArithmeticFunction::from_fn(200, |k| M::from(k * k + 1));
+        assert_eq!(&f * &f.dirichlet_inverse(), ArithmeticFunction::unit(200));
+    }
+}
@@ -0,0 +1,118 @@
+//! Discrete logarithm in modular arithmetic
+
+use std::collections::HashMap;
+use crate::int64::crt;
+use crate::traits::{ModInt, ModUnary};
+
+/// All solutions of a discrete logarithm problem, which are x0 + k * period for k = 0, 1, 2, ...
+/// The period is zero if x0 is the only solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscreteLog {
+    pub x0: u64,
+    pub period: u64
+}
+
+impl DiscreteLog {
+    /// Test if x is one of the solutions
+    pub fn contains(&self, x: u64) -> bool {
+        match self.period {
+            0 => x == self.x0,
+            _ => x >= self.x0 && (x - self.x0).is_multiple_of(self.period)
+        }
+    }
+}
+
+/// Returns the least x in [0, bound) such that a^x ≡ b (mod n) by baby-step giant-step
+/// algorithm, a has to be coprime to n. Both time and space cost are O(sqrt(bound)).
+pub fn bsgs(a: u64, b: u64, n: u64, bound: u64) -> Option<u64> {
+    let (a, b) = (a % n, b % n);
+    let m = num_integer::sqrt(bound) + 1; // m^2 > bound
+
+    // baby steps a^j, keep the smallest j for each value
+    let mut table = HashMap::with_capacity(m as usize);
+    let mut power = 1 % n;
+    for j in 0..m {
+        table.entry(power).or_insert(j);
+        power = power.mul_mod(a, &n);
+    }
+
+    // giant steps b * a^(-im), the first hit is the least solution
+    let giant = power.inv_mod(&n)?;
+    let mut gamma = b;
+    for i in 0..m {
+        if let Some(&j) = table.get(&gamma) {
+            let x = i * m + j;
+            return if x < bound { Some(x) } else { None };
+        }
+        gamma = gamma.mul_mod(giant, &n);
+    }
+    None
+}
+
+/// Returns the least x such that a^x ≡ b (mod n) by Pohlig-Hellman algorithm, where `order`
+/// is the multiplicative order of a modulo n and `factors` is its factorization. The solutions
+/// in each Sylow subgroup are found digit by digit with `bsgs`.
+pub fn pohlig_hellman(a: u64, b: u64, n: u64, order: u64, factors: &HashMap<u64, usize>) -> Option<u64> {
+    let (a, b) = (a % n, b % n);
+    let mut congruences = Vec::with_capacity(factors.len());
+    for (&q, &k) in factors {
+        // g = a^(order/q^k) has order q^k
+        let qk = q.pow(k as u32);
+        let g = a.pow_mod(order / qk, &n);
+        let h = b.pow_mod(order / qk, &n);
+        let gamma = g.pow_mod(qk / q, &n); // order q
+
+        // find x = sum(d_i * q^i) such that g^x = h
+        let mut x = 0;
+        let mut qi = 1;
+        for _ in 0..k {
+            let hi = g.pow_mod(x, &n).inv_mod(&n)?.mul_mod(h, &n).pow_mod(qk / qi / q, &n);
+            x += bsgs(gamma, hi, n, q)? * qi;
+            qi *= q;
+        }
+        congruences.push((x, qk));
+    }
+
+    let (x, _) = crt(&congruences)?;
+    if a.pow_mod(x, &n) == b { Some(x) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::PrimeBuffer;
+
+    #[test]
+    fn bsgs_test() {
+        for &p in &[2u64, 3, 7, 13, 31, 97, 101] {
+            for a in 1..p {
+                for b in 0..p {
+                    let expected = (0..p).find(|&x| a.pow_mod(x, &p) == b);
+                    assert_eq!(bsgs(a, b, p, p), expected);
+                }
+            }
+        }
+        let p = 1000000007u64;
+        assert_eq!(bsgs(5, 5u64.pow_mod(123456789, &p), p, p), Some(123456789));
+    }
+
+    #[test]
+    fn pohlig_hellman_test() {
+        let mut pb = PrimeBuffer::new();
+        for &n in &[9u64, 25, 27, 49, 64, 81, 97, 125, 128, 243] {
+            for a in (1..n).filter(|a| num_integer::Integer::gcd(a, &n) == 1) {
+                let order = pb.order(a, n).unwrap();
+                let factors = if order == 1 { HashMap::new() } else { pb.factors(order) };
+                for b in 0..n {
+                    let expected = (0..order).find(|&x| a.pow_mod(x, &n) == b);
+                    assert_eq!(pohlig_hellman(a, b, n, order, &factors), expected);
+                }
+            }
+        }
+
+        // 998244353 - 1 = 2^23 * 7 * 17
+        let (p, x) = (998244353u64, 987654321u64);
+        let factors = pb.factors(p - 1);
+        assert_eq!(pohlig_hellman(3, 3u64.pow_mod(x, &p), p, p - 1, &factors), Some(x));
+    }
+}
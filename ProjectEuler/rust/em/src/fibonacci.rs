@@ -0,0 +1,135 @@
+//! Fibonacci and Lucas numbers
+//!
+//! F(n) and L(n) are computed by the fast doubling formulas
+//!   F(2k) = F(k) * (2F(k+1) - F(k)),  F(2k+1) = F(k)^2 + F(k+1)^2
+//! in O(log n) steps. The Pisano period is provided by `PrimeBuffer::pisano_period`.
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use crate::traits::ModInt;
+
+/// Returns (F(n), F(n+1)) mod m
+pub fn fibonacci_pair_mod(n: u64, m: u64) -> (u64, u64) {
+    let (mut a, mut b) = (0, 1 % m); // (F(k), F(k+1))
+    for i in (0..64 - n.leading_zeros()).rev() {
+        let c = a.mul_mod(b.add_mod(b, &m).sub_mod(a, &m), &m);
+        let d = a.mul_mod(a, &m).add_mod(b.mul_mod(b, &m), &m);
+        if (n >> i) & 1 == 0 {
+            a = c;
+            b = d;
+        } else {
+            a = d;
+            b = c.add_mod(d, &m);
+        }
+    }
+    (a, b)
+}
+
+/// Returns F(n) mod m
+pub fn fibonacci_mod(n: u64, m: u64) -> u64 {
+    fibonacci_pair_mod(n, m).0
+}
+
+/// Returns L(n) = 2F(n+1) - F(n) mod m
+pub fn lucas_mod(n: u64, m: u64) -> u64 {
+    let (a, b) = fibonacci_pair_mod(n, m);
+    b.add_mod(b, &m).sub_mod(a, &m)
+}
+
+/// Returns (F(n), F(n+1))
+pub fn fibonacci_pair(n: u64) -> (BigUint, BigUint) {
+    let (mut a, mut b) = (BigUint::zero(), BigUint::one());
+    for i in (0..64 - n.leading_zeros()).rev() {
+        let c = &a * (&b + &b - &a);
+        let d = &a * &a + &b * &b;
+        if (n >> i) & 1 == 0 {
+            a = c;
+            b = d;
+        } else {
+            b = c + &d;
+            a = d;
+        }
+    }
+    (a, b)
+}
+
+/// Returns the Fibonacci number F(n)
+pub fn fibonacci(n: u64) -> BigUint {
+    fibonacci_pair(n).0
+}
+
+/// Returns the Lucas number L(n)
+pub fn lucas(n: u64) -> BigUint {
+    let (a, b) = fibonacci_pair(n);
+    &b + &b - a
+}
+
+/// Returns the Zeckendorf representation of n, which is the unique way to write n as a
+/// sum of non-consecutive Fibonacci numbers F(k) with k >= 2. The indices k are returned
+/// in decreasing order.
+pub fn zeckendorf(mut n: u64) -> Vec<u32> {
+    // the Fibonacci numbers F(2), F(3), ... not larger than n
+    let mut fibs = vec![1u64, 2];
+    while let Some(next) = fibs[fibs.len() - 1].checked_add(fibs[fibs.len() - 2]) {
+        if next > n {
+            break;
+        }
+        fibs.push(next);
+    }
+
+    // greedily take the largest Fibonacci number
+    let mut result = Vec::new();
+    for (i, &f) in fibs.iter().enumerate().rev() {
+        if f <= n {
+            n -= f;
+            result.push(i as u32 + 2);
+        }
+    }
+    result
+}
+
+/// Returns the sum of F(k) for the indices k, which decodes the Zeckendorf representation
+pub fn from_zeckendorf(indices: &[u32]) -> u64 {
+    // F(k) is exact modulo u64::MAX as long as it fits in u64
+    indices.iter().map(|&k| fibonacci_mod(k as u64, u64::MAX)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci_test() {
+        let mut fibs = vec![0u64, 1];
+        for i in 2..94 {
+            fibs.push(fibs[i - 1] + fibs[i - 2]);
+        }
+        for (n, &f) in fibs.iter().enumerate() {
+            assert_eq!(fibonacci(n as u64), BigUint::from(f));
+            assert_eq!(fibonacci_mod(n as u64, 1000000007), f % 1000000007);
+            assert_eq!(fibonacci_mod(n as u64, u64::MAX), f);
+            if n + 1 < fibs.len() {
+                let l = 2 * fibs[n + 1] as u128 - f as u128;
+                assert_eq!(lucas(n as u64), BigUint::from(l));
+                assert_eq!(lucas_mod(n as u64, 1000), (l % 1000) as u64);
+            }
+        }
+        assert_eq!(fibonacci_mod(5, 1), 0);
+        assert_eq!(fibonacci(300).to_string(), "222232244629420445529739893461909967206666939096499764990979600");
+        assert_eq!(fibonacci_mod(1000000000000000000, 1000000007), 209783453);
+        assert_eq!(lucas_mod(1000000000000000000, 1000000007), 150331332);
+    }
+
+    #[test]
+    fn zeckendorf_test() {
+        assert_eq!(zeckendorf(0), Vec::<u32>::new());
+        assert_eq!(zeckendorf(100), [11, 6, 4]); // 89 + 8 + 3
+        for n in 0..10000 {
+            let z = zeckendorf(n);
+            assert!(z.windows(2).all(|w| w[0] >= w[1] + 2));
+            assert_eq!(from_zeckendorf(&z), n);
+        }
+        let z = zeckendorf(u64::MAX);
+        assert_eq!(from_zeckendorf(&z), u64::MAX);
+    }
+}
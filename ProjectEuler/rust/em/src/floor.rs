@@ -0,0 +1,125 @@
+//! Sums involving floor division
+//!
+//! floor(n / i) takes only O(sqrt(n)) distinct values, so sums like sum(f(floor(n / i)))
+//! can be evaluated block by block with `QuotientBlocks`. The linear floor sums
+//! sum(floor((a*i + b) / m)) are computed by the Euclidean-like reduction in `floor_sum`.
+
+use std::ops::RangeInclusive;
+use num_integer::Integer;
+
+/// Iterator over the distinct values q = floor(n / i) for i >= 1, yielding each value with
+/// the range of i giving it. The values are decreasing and the ranges are increasing.
+pub struct QuotientBlocks {
+    n: u64,
+    next: Option<u64> // the next i, None when exhausted
+}
+
+impl QuotientBlocks {
+    pub fn new(n: u64) -> Self {
+        QuotientBlocks { n, next: if n == 0 { None } else { Some(1) } }
+    }
+}
+
+impl Iterator for QuotientBlocks {
+    type Item = (u64, RangeInclusive<u64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lo = self.next?;
+        let q = self.n / lo;
+        let hi = self.n / q;
+        self.next = if hi < self.n { Some(hi + 1) } else { None };
+        Some((q, lo..=hi))
+    }
+}
+
+/// Returns the distinct values of floor(n / i) in increasing order. There are about
+/// 2 * sqrt(n) of them, each value v is at position v - 1 if v <= sqrt(n).
+pub fn quotient_values(n: u64) -> Vec<u64> {
+    let mut values: Vec<u64> = QuotientBlocks::new(n).map(|(q, _)| q).collect();
+    values.reverse();
+    values
+}
+
+/// Returns sum(floor((a*i + b) / m) for i in 0..n), n has to be non-negative and m positive.
+///
+/// The coefficients can be negative for signed types, the division rounds towards negative
+/// infinity. The intermediate value a*n + b (after reducing a and b modulo m) and the
+/// result must fit in T, so use `u128` or `BigInt` for large inputs.
+pub fn floor_sum<T: Clone + Integer>(n: T, m: T, a: T, b: T) -> T {
+    assert!(n >= T::zero() && m > T::zero());
+    let (mut n, mut m, mut a, mut b) = (n, m, a, b);
+    let two = T::one() + T::one();
+    let mut result = T::zero();
+    while !n.is_zero() {
+        // take out floor(a / m) * i and floor(b / m), then a, b < m
+        let (q, r) = a.div_mod_floor(&m);
+        if !q.is_zero() {
+            let pairs = if n.is_even() { n.clone() / two.clone() * (n.clone() - T::one()) }
+                        else { (n.clone() - T::one()) / two.clone() * n.clone() };
+            result = result + pairs * q;
+            a = r;
+        }
+        let (q, r) = b.div_mod_floor(&m);
+        result = result + n.clone() * q;
+        b = r;
+
+        // count the lattice points under the line by swapping the axes
+        let y_max = a.clone() * n + b;
+        if y_max < m {
+            break;
+        }
+        let (q, r) = y_max.div_rem(&m);
+        n = q;
+        b = r;
+        std::mem::swap(&mut m, &mut a);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn quotient_test() {
+        for n in 0..500u64 {
+            let mut i = 1;
+            for (q, range) in QuotientBlocks::new(n) {
+                assert_eq!(*range.start(), i);
+                assert!(range.clone().all(|j| n / j == q));
+                i = range.end() + 1;
+            }
+            assert_eq!(i, n + 1);
+        }
+
+        // sum of the divisor counts d(1) + ... + d(n) = sum(floor(n / i))
+        let n = 1000000000000u64;
+        let blocks: u64 = QuotientBlocks::new(n).map(|(q, r)| q * (r.end() - r.start() + 1)).sum();
+        assert_eq!(blocks, 27785452449086);
+
+        let values = quotient_values(100);
+        assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 14, 16, 20, 25, 33, 50, 100]);
+        assert!(quotient_values(0).is_empty());
+    }
+
+    #[test]
+    fn floor_sum_test() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..1000 {
+            let (n, m) = (rng.gen_range(0..50i64), rng.gen_range(1..50i64));
+            let (a, b) = (rng.gen_range(-100..100i64), rng.gen_range(-100..100i64));
+            let expected: i64 = (0..n).map(|i| (a * i + b).div_euclid(m)).sum();
+            assert_eq!(floor_sum(n, m, a, b), expected);
+            assert_eq!(floor_sum(BigInt::from(n), BigInt::from(m), BigInt::from(a), BigInt::from(b)), BigInt::from(expected));
+            if a >= 0 && b >= 0 {
+                assert_eq!(floor_sum(n as u64, m as u64, a as u64, b as u64), expected as u64);
+            }
+        }
+
+        // sum(floor(i * p / q)) = (p - 1)(q - 1) / 2 for coprime p, q
+        let (p, q) = (1000000000000000003u128, 999999999999999989u128);
+        assert_eq!(floor_sum(q, q, p, 0), (p - 1) * (q - 1) / 2);
+    }
+}
@@ -1,7 +1,6 @@
-use num_traits::{FromPrimitive, Zero, One, RefNum, NumRef};
+use num_traits::{Zero, One, RefNum, NumRef};
 use num_integer::{Integer, sqrt};
 use num_bigint::BigInt;
-use std::ops::Neg;
 use std::fmt;
 
 /// A type representation quadratic surd number (a + b*sqrt(r)) / c
@@ -19,7 +18,7 @@ impl<T> QuadraticSurd<T> {
 
 impl<T> QuadraticSurd<T> 
 where T: Integer + NumRef,
-// for<'r> &'r T: RefNum<T>
+for<'r> &'r T: RefNum<T>
 {
     // TODO: add method to reduce root r
 
@@ -30,9 +29,9 @@ where T: Integer + NumRef,
 
         // reduce common divisor
         let g = self.a.gcd(&self.b).gcd(&self.c);
-        self.a = self.a / &g;
-        self.b = self.b / &g;
-        self.c = self.c / g;
+        self.a = &self.a / &g;
+        self.b = &self.b / &g;
+        self.c = &self.c / &g;
 
         // keep denom positive
         if self.c < T::zero() {
@@ -81,7 +80,7 @@ impl QuadraticSurd<i64> {
         let ar = if self.a >= 0 { ar } else { -(ar + 1) };
         let nom = ar + self.b;
         let nom = if nom >= 0 { nom } else {nom - self.c + 1};
-        return nom / self.c;
+        nom / self.c
     }
 
     pub fn value(&self) -> f64 {
@@ -100,6 +99,23 @@ impl QuadraticSurd<BigInt> {
         let nom = ar + &self.b;
         let nom = if nom >= BigInt::zero() { nom }
                   else {nom - &self.c + 1};
-        return nom / &self.c;
+        nom / &self.c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduce_test() {
+        // (2 + 4√5) / -6 = (-1 - 2√5) / 3
+        let x = QuadraticSurd::new(2i64, 4, -6, 5);
+        assert!(x == QuadraticSurd::new_raw(-1, -2, 3, 5));
+        let x = QuadraticSurd::new(BigInt::from(2), BigInt::from(4), BigInt::from(-6), BigInt::from(5));
+        assert_eq!(x.to_string(), "(-1 + -2√5) / 3");
+
+        // 1 / √2 = √2 / 2
+        assert!(QuadraticSurd::from_sqrt(2i64).recip() == QuadraticSurd::new_raw(0, 1, 2, 2));
     }
 }
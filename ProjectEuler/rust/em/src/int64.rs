@@ -1,5 +1,5 @@
 use num_integer::sqrt;
-use crate::traits::{ModInt, ArithmeticHelpers};
+use crate::traits::{ModInt, ModUnary, ArithmeticHelpers};
 
 /// Returns floor(log(2, target))
 #[inline]
@@ -26,6 +26,110 @@ pub fn is_sq(target: u64) -> bool {
     s * s == target
 }
 
+/// Returns (g, x, y) such that g = gcd(a, b) and a*x + b*y = g
+pub fn extended_gcd(a: u64, b: u64) -> (u64, i128, i128) {
+    let (mut r0, mut r1) = (a as i128, b as i128);
+    let (mut x0, mut x1) = (1i128, 0i128);
+    let (mut y0, mut y1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    (r0 as u64, x0, y0)
+}
+
+/// Solve the system of congruences x ≡ a_i (mod m_i) given as pairs (a_i, m_i). The
+/// moduli don't need to be coprime. Returns (x, lcm of m_i) with x < lcm, or None if
+/// the congruences are incompatible.
+///
+/// The intermediate products don't overflow, but the lcm of the moduli must fit in u64.
+pub fn crt(congruences: &[(u64, u64)]) -> Option<(u64, u64)> {
+    let (mut x, mut m) = (0u64, 1u64);
+    for &(a2, m2) in congruences {
+        assert!(m2 > 0);
+
+        // x + m*t ≡ a2 (mod m2) => (m/g)*t ≡ (a2 - x)/g (mod m2/g)
+        let (g, inv, _) = extended_gcd(m, m2);
+        let diff = (&a2).sub_mod(&x, &m2);
+        if diff % g != 0 {
+            return None;
+        }
+        let m2g = m2 / g;
+        let inv = inv.rem_euclid(m2g as i128) as u64;
+        let t = (diff / g).mul_mod(inv, &m2g);
+
+        let lcm = (m as u128) * (m2g as u128);
+        assert!(lcm <= u64::MAX as u128, "the lcm of the moduli overflows u64");
+        x = ((x as u128 + (m as u128) * (t as u128)) % lcm) as u64;
+        m = lcm as u64;
+    }
+    Some((x, m))
+}
+
+/// Returns 1 + a + a^2 + ... + a^(n-1) mod m. It doesn't divide by a - 1, so it works
+/// for any a and m.
+pub fn geometric_sum_mod(a: u64, n: u64, m: u64) -> u64 {
+    // (S(k), a^k) with S(2k) = S(k) * (1 + a^k) and S(k+1) = S(k) + a^k
+    let (mut sum, mut power) = (0, 1 % m);
+    for i in (0..64 - n.leading_zeros()).rev() {
+        sum = sum.mul_mod(power.add_mod(1, &m), &m);
+        power = power.mul_mod(power, &m);
+        if (n >> i) & 1 == 1 {
+            sum = sum.add_mod(power, &m);
+            power = power.mul_mod(a, &m);
+        }
+    }
+    sum
+}
+
+/// Returns the Jacobi symbol (a / n), n has to be odd. It equals the Legendre symbol
+/// when n is a prime.
+pub fn jacobi(a: u64, n: u64) -> i8 {
+    assert!(n & 1 == 1);
+    let (mut a, mut n) = (a % n, n);
+    let mut t = 1;
+    while a != 0 {
+        let shift = a.trailing_zeros();
+        a >>= shift;
+        if shift & 1 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            t = -t;
+        }
+        if a % 4 == 3 && n % 4 == 3 {
+            t = -t;
+        }
+        (a, n) = (n % a, a);
+    }
+    if n == 1 { t } else { 0 }
+}
+
+/// Returns the Kronecker symbol (a / n), which extends the Jacobi symbol to all n
+pub fn kronecker(a: i64, n: i64) -> i8 {
+    if n == 0 {
+        return (a == 1 || a == -1) as i8;
+    }
+
+    // (a / -1) = -1 iff a < 0
+    let mut t = if n < 0 && a < 0 { -1 } else { 1 };
+    let n = n.unsigned_abs();
+
+    // (a / 2) = 0 for even a, otherwise -1 iff a ≡ 3, 5 (mod 8)
+    let shift = n.trailing_zeros();
+    if shift > 0 {
+        if a & 1 == 0 {
+            return 0;
+        }
+        let a8 = a.rem_euclid(8);
+        if shift & 1 == 1 && (a8 == 3 || a8 == 5) {
+            t = -t;
+        }
+    }
+
+    let n = n >> shift;
+    t * jacobi(a.rem_euclid(n as i64) as u64, n)
+}
+
 impl ArithmeticHelpers for u64 {
     /// Returns greatest common divisor between a, b
     #[inline]
@@ -40,26 +144,7 @@ impl ModInt<&u64, &u64> for &u64 {
             return ab % m
         }
 
-        let mut a = self % m;
-        let mut b = rhs % m;
-
-        if let Some(ab) = a.checked_mul(b) {
-            return ab % m
-        }
-
-        let mut result: u64 = 0;
-        while b > 0 {
-            if b & 1 > 0 {
-                result += a;
-                result %= m;
-            }
-            a <<= 1;
-            if a >= *m {
-                a %= m;
-            }
-            b >>= 1;
-        }
-        result
+        ((*self as u128) * (*rhs as u128) % (*m as u128)) as u64
     }
 
     fn pow_mod(self, exp: &u64, m: &u64) -> u64 {
@@ -85,6 +170,34 @@ impl ModInt<&u64, &u64> for &u64 {
         }
         result
     }
+
+    fn add_mod(self, rhs: &u64, m: &u64) -> u64 {
+        let (a, b) = (self % m, rhs % m);
+        if a >= m - b { a - (m - b) } else { a + b }
+    }
+
+    fn sub_mod(self, rhs: &u64, m: &u64) -> u64 {
+        let (a, b) = (self % m, rhs % m);
+        if a >= b { a - b } else { m - (b - a) }
+    }
+}
+
+impl ModUnary<&u64> for &u64 {
+    type Output = u64;
+
+    fn neg_mod(self, m: &u64) -> u64 {
+        match self % m {
+            0 => 0, a => m - a
+        }
+    }
+
+    fn inv_mod(self, m: &u64) -> Option<u64> {
+        let (g, x, _) = extended_gcd(self % m, *m);
+        if g != 1 {
+            return None;
+        }
+        Some(x.rem_euclid(*m as i128) as u64)
+    }
 }
 
 impl ModInt<u64, &u64> for &u64 {
@@ -93,11 +206,16 @@ impl ModInt<u64, &u64> for &u64 {
     fn mul_mod(self, rhs: u64, m: &u64) -> u64 { self.mul_mod(&rhs, m) }
     #[inline]
     fn pow_mod(self, exp: u64, m: &u64) -> u64 { self.pow_mod(&exp, m) }
+    #[inline]
+    fn add_mod(self, rhs: u64, m: &u64) -> u64 { self.add_mod(&rhs, m) }
+    #[inline]
+    fn sub_mod(self, rhs: u64, m: &u64) -> u64 { self.sub_mod(&rhs, m) }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::traits::Arithmetic;
 
     #[test]
     fn integer_test() {
@@ -113,5 +231,94 @@ mod tests {
         let m = rand::random::<u64>() % 100000;
         assert_eq!(a.mul_mod(a, &m), (a * a) % m);
         assert_eq!(a.pow_mod(3, &m), a.pow(3) % m);
+
+        let b = rand::random::<u64>() % 100000;
+        assert_eq!(a.add_mod(b, &m), (a + b) % m);
+        assert_eq!(a.sub_mod(b, &m), (a + m * 100000 - b) % m);
+        assert_eq!((a + b).sub_mod(b, &m), a % m);
+        assert_eq!(a.add_mod(a.neg_mod(&m), &m), 0);
+
+        // moduli above 2^63
+        let big = u64::MAX - 58; // the largest prime below 2^64
+        assert_eq!((big - 1).mul_mod(big - 1, &big), 1);
+        assert_eq!((big - 2).mul_mod(big - 3, &big), 6);
+        assert_eq!(2u64.pow_mod(big - 1, &big), 1);
+        assert_eq!((big - 1).add_mod(big - 2, &big), big - 3);
+        assert_eq!(3u64.sub_mod(big - 1, &big), 4);
+        let inv = 12345u64.inv_mod(&big).unwrap();
+        assert_eq!(12345u64.mul_mod(inv, &big), 1);
+    }
+
+    #[test]
+    fn geometric_test() {
+        for (a, m) in [(3u64, 1000u64), (11, 100), (10, 9), (0, 7), (5, 1)] {
+            let mut sum = 0;
+            for n in 0..50 {
+                assert_eq!(geometric_sum_mod(a, n, m), sum);
+                sum = sum.add_mod(a.pow_mod(n, &m), &m);
+            }
+        }
+        let big = u64::MAX - 58;
+        assert_eq!(geometric_sum_mod(1, u64::MAX, big), u64::MAX % big);
+        let expected = (2u64).pow_mod(1000000, &big).sub_mod(1, &big);
+        assert_eq!(geometric_sum_mod(2, 1000000, big), expected);
+    }
+
+    #[test]
+    fn symbol_test() {
+        // compare with Euler's criterion
+        for p in [3u64, 5, 7, 11, 13, 97, 65537] {
+            for a in 0..200 {
+                let e = a.pow_mod((p - 1) / 2, &p);
+                let expected = if e == 0 { 0 } else if e == 1 { 1 } else { -1 };
+                assert_eq!(jacobi(a, p), expected);
+            }
+        }
+        assert_eq!(jacobi(1001, 9907), -1);
+        assert_eq!(jacobi(19, 45), 1);
+        assert_eq!(jacobi(8, 21), -1);
+        assert_eq!(jacobi(5, 21), 1);
+        assert_eq!(jacobi(u64::MAX - 1, u64::MAX), -jacobi(1, u64::MAX));
+
+        assert_eq!(kronecker(0, 0), 0);
+        assert_eq!(kronecker(-1, 0), 1);
+        assert_eq!(kronecker(3, 2), -1);
+        assert_eq!(kronecker(7, 2), 1);
+        assert_eq!(kronecker(4, 6), 0);
+        assert_eq!(kronecker(-5, -3), -1);
+        assert_eq!(kronecker(5, -3), -1);
+        assert_eq!(kronecker(-3, 20), -1);
+        for a in -30i64..30 {
+            for n in 1..30i64 {
+                for m in 1..30i64 { // multiplicative in the denominator
+                    assert_eq!(kronecker(a, n * m), kronecker(a, n) * kronecker(a, m));
+                }
+                if n & 1 == 1 {
+                    assert_eq!(kronecker(a, n), jacobi(a.rem_euclid(n) as u64, n as u64));
+                    assert_eq!(kronecker(a, n), Arithmetic::jacobi(&(a.rem_euclid(n) as u64), &(n as u64)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gcd_test() {
+        for &(a, b) in &[(240u64, 46u64), (0, 7), (7, 0), (u64::MAX, u64::MAX - 1), (1 << 40, 6 << 20)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, num_integer::gcd(a, b));
+            assert_eq!(a as i128 * x + b as i128 * y, g as i128);
+        }
+
+        assert_eq!(3u64.inv_mod(&7), Some(5));
+        assert_eq!(4u64.inv_mod(&8), None);
+
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(3, 4), (5, 6)]), Some((11, 12)));
+        assert_eq!(crt(&[(3, 4), (4, 6)]), None);
+
+        let (p, q) = (u32::MAX as u64 - 4, u32::MAX as u64 - 16); // two primes
+        let x = 0xDEADBEEFCAFEu64 % (p * q);
+        assert_eq!(crt(&[(x % p, p), (x % q, q)]), Some((x, p * q)));
     }
 }
@@ -1,7 +1,7 @@
-use num_bigint::BigUint;
-use num_integer::Integer;
-use num_traits::{Zero, ToPrimitive};
-use crate::traits::{ModInt, ArithmeticHelpers};
+use num_bigint::{BigInt, BigUint};
+use num_integer::{Integer, ExtendedGcd};
+use num_traits::{Zero, One, Signed, ToPrimitive};
+use crate::traits::{ModInt, ModUnary, Arithmetic, ArithmeticHelpers};
 
 /// Returns floor(log(2, target))
 /// XXX: This api is introduced in nightly Rust
@@ -19,9 +19,9 @@ pub fn log(target: &BigUint, base: &BigUint) -> u64 {
     assert_ne!(target, &BigUint::zero());
 
     let mut counter: u64 = 1;
-    let mut v = target.div_floor(&base);
+    let mut v = target.div_floor(base);
     while v > BigUint::zero() {
-        v = v.div_floor(&base);
+        v = v.div_floor(base);
         counter += 1;
     }
     counter
@@ -33,11 +33,91 @@ pub fn is_sq(target: &BigUint) -> bool {
     s.clone() * s == *target
 }
 
+/// Returns the Jacobi symbol (a / n), n has to be odd. It equals the Legendre symbol
+/// when n is a prime.
+#[inline]
+pub fn jacobi(a: &BigUint, n: &BigUint) -> i8 {
+    a.jacobi(n)
+}
+
+/// Returns the Kronecker symbol (a / n), which extends the Jacobi symbol to all n
+pub fn kronecker(a: &BigInt, n: &BigInt) -> i8 {
+    if n.is_zero() {
+        return (a.abs().is_one()) as i8;
+    }
+
+    // (a / -1) = -1 iff a < 0
+    let mut t = if n.is_negative() && a.is_negative() { -1 } else { 1 };
+    let n = n.magnitude();
+
+    // (a / 2) = 0 for even a, otherwise -1 iff a ≡ 3, 5 (mod 8)
+    let shift = n.trailing_zeros().unwrap_or(0);
+    if shift > 0 {
+        if a.is_even() {
+            return 0;
+        }
+        let a8 = a.mod_floor(&BigInt::from(8u8));
+        if shift & 1 == 1 && (a8 == BigInt::from(3u8) || a8 == BigInt::from(5u8)) {
+            t = -t;
+        }
+    }
+
+    let n = n >> shift;
+    let a = a.mod_floor(&BigInt::from(n.clone())).to_biguint().unwrap();
+    t * jacobi(&a, &n)
+}
+
+/// Returns (g, x, y) such that g = gcd(a, b) and a*x + b*y = g
+pub fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let ExtendedGcd { gcd, x, y, .. } = a.extended_gcd(b);
+    (gcd, x, y)
+}
+
+/// Solve the system of congruences x ≡ a_i (mod m_i) given as pairs (a_i, m_i). The
+/// moduli don't need to be coprime. Returns (x, lcm of m_i) with x < lcm, or None if
+/// the congruences are incompatible.
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<(BigUint, BigUint)> {
+    let (mut x, mut m) = (BigUint::zero(), BigUint::one());
+    for (a2, m2) in congruences {
+        assert!(!m2.is_zero());
+
+        // x + m*t ≡ a2 (mod m2) => (m/g)*t ≡ (a2 - x)/g (mod m2/g)
+        let (g, inv, _) = extended_gcd(&BigInt::from(m.clone()), &BigInt::from(m2.clone()));
+        let g = g.to_biguint().unwrap();
+        let diff = a2.sub_mod(&x, m2);
+        if !diff.is_multiple_of(&g) {
+            return None;
+        }
+        let m2g = m2 / &g;
+        let inv = inv.mod_floor(&BigInt::from(m2g.clone())).to_biguint().unwrap();
+        let t = (diff / g).mul_mod(&inv, &m2g);
+
+        x += &m * t;
+        m *= m2g;
+    }
+    Some((x, m))
+}
+
+/// Returns 1 + a + a^2 + ... + a^(n-1) mod m, for any a and m
+pub fn geometric_sum_mod(a: &BigUint, n: &BigUint, m: &BigUint) -> BigUint {
+    // (S(k), a^k) with S(2k) = S(k) * (1 + a^k) and S(k+1) = S(k) + a^k
+    let (mut sum, mut power) = (BigUint::zero(), BigUint::one() % m);
+    for i in (0..n.bits()).rev() {
+        sum = sum.mul_mod(&power + 1u8, m);
+        power = (&power).mul_mod(&power, m);
+        if n.bit(i) {
+            sum = sum.add_mod(&power, m);
+            power = power.mul_mod(a, m);
+        }
+    }
+    sum
+}
+
 impl ArithmeticHelpers for BigUint {
     /// Returns greatest common divisor between a, b
     #[inline]
     fn trailing_zeros(&self) -> usize { 
-        match BigUint::trailing_zeros(&self) {
+        match BigUint::trailing_zeros(self) {
             Some(a) => a as usize, None => 0
         }
     }
@@ -61,16 +141,53 @@ impl ModInt<&BigUint, &BigUint> for &BigUint {
 
     #[inline]
     fn pow_mod(self, exp: &BigUint, m: &BigUint) -> BigUint {
-        self.modpow(&exp, m)
+        self.modpow(exp, m)
+    }
+
+    fn add_mod(self, rhs: &BigUint, m: &BigUint) -> BigUint {
+        (self % m + rhs % m) % m
+    }
+
+    fn sub_mod(self, rhs: &BigUint, m: &BigUint) -> BigUint {
+        let (a, b) = (self % m, rhs % m);
+        if a >= b { a - b } else { m - (b - a) }
     }
 }
 
+impl ModUnary<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn neg_mod(self, m: &BigUint) -> BigUint {
+        let a = self % m;
+        if a.is_zero() { a } else { m - a }
+    }
+
+    fn inv_mod(self, m: &BigUint) -> Option<BigUint> {
+        let (g, x, _) = extended_gcd(&BigInt::from(self % m), &BigInt::from(m.clone()));
+        if !g.is_one() {
+            return None;
+        }
+        x.mod_floor(&BigInt::from(m.clone())).to_biguint()
+    }
+}
+impl ModUnary<&BigUint> for BigUint {
+    type Output = BigUint;
+    #[inline]
+    fn neg_mod(self, m: &BigUint) -> BigUint { (&self).neg_mod(m) }
+    #[inline]
+    fn inv_mod(self, m: &BigUint) -> Option<BigUint> { (&self).inv_mod(m) }
+}
+
 impl ModInt<BigUint, &BigUint> for BigUint {
     type Output = BigUint;
     #[inline]
     fn mul_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.mul_mod(&rhs, m) }
     #[inline]
     fn pow_mod(self, exp: BigUint, m: &BigUint) -> BigUint { self.pow_mod(&exp, m) }
+    #[inline]
+    fn add_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.add_mod(&rhs, m) }
+    #[inline]
+    fn sub_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.sub_mod(&rhs, m) }
 }
 impl ModInt<BigUint, &BigUint> for &BigUint {
     type Output = BigUint;
@@ -78,6 +195,10 @@ impl ModInt<BigUint, &BigUint> for &BigUint {
     fn mul_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.mul_mod(&rhs, m) }
     #[inline]
     fn pow_mod(self, exp: BigUint, m: &BigUint) -> BigUint { self.pow_mod(&exp, m) }
+    #[inline]
+    fn add_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.add_mod(&rhs, m) }
+    #[inline]
+    fn sub_mod(self, rhs: BigUint, m: &BigUint) -> BigUint { self.sub_mod(&rhs, m) }
 }
 impl ModInt<&BigUint, &BigUint> for BigUint {
     type Output = BigUint;
@@ -85,6 +206,10 @@ impl ModInt<&BigUint, &BigUint> for BigUint {
     fn mul_mod(self, rhs: &BigUint, m: &BigUint) -> BigUint { (&self).mul_mod(rhs, m) }
     #[inline]
     fn pow_mod(self, exp: &BigUint, m: &BigUint) -> BigUint { (&self).pow_mod(exp, m) }
+    #[inline]
+    fn add_mod(self, rhs: &BigUint, m: &BigUint) -> BigUint { (&self).add_mod(rhs, m) }
+    #[inline]
+    fn sub_mod(self, rhs: &BigUint, m: &BigUint) -> BigUint { (&self).sub_mod(rhs, m) }
 }
 
 #[cfg(test)]
@@ -99,5 +224,60 @@ mod tests {
         let m = rng.gen_biguint(500); let rm = &m;
         assert_eq!(ra.mul_mod(ra, rm), (ra * ra) % rm);
         assert_eq!(ra.pow_mod(BigUint::from(3u8), rm), ra.pow(3) % rm);
+
+        let b = rng.gen_biguint(500); let rb = &b;
+        assert_eq!(ra.add_mod(rb, rm), (ra + rb) % rm);
+        assert_eq!((ra + rb).sub_mod(rb, rm), ra % rm);
+        assert_eq!(ra.add_mod(ra.neg_mod(rm), rm), BigUint::zero());
+
+        let m127 = BigUint::from(2u8).pow(127u32) - 1u8;
+        let inv = ra.inv_mod(&m127).unwrap();
+        assert_eq!(ra.mul_mod(&inv, &m127), BigUint::one());
+        assert_eq!(BigUint::from(6u8).inv_mod(&BigUint::from(9u8)), None);
+    }
+
+    #[test]
+    fn symbol_test() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let a = rng.gen_biguint(64).to_u64().unwrap();
+            let n = rng.gen_biguint(63).to_u64().unwrap() | 1;
+            assert_eq!(jacobi(&BigUint::from(a), &BigUint::from(n)), crate::int64::jacobi(a, n));
+
+            let (sa, sn) = (a as i64, (n as i64) << 2);
+            assert_eq!(kronecker(&BigInt::from(sa), &BigInt::from(-sn)), crate::int64::kronecker(sa, -sn));
+        }
+
+        // 2^127 - 1 is a prime, (3 / p) = -1 since p ≡ 1 (mod 3) and p ≡ 3 (mod 4)
+        let m127 = BigUint::from(2u8).pow(127u32) - 1u8;
+        assert_eq!(jacobi(&BigUint::from(3u8), &m127), -1);
+        assert_eq!(jacobi(&BigUint::from(2u8), &m127), 1);
+    }
+
+    #[test]
+    fn crt_test() {
+        let big = |x: u64| BigUint::from(x);
+        assert_eq!(crt(&[(big(2), big(3)), (big(3), big(5)), (big(2), big(7))]), Some((big(23), big(105))));
+        assert_eq!(crt(&[(big(3), big(4)), (big(5), big(6))]), Some((big(11), big(12))));
+        assert_eq!(crt(&[(big(3), big(4)), (big(4), big(6))]), None);
+
+        let m61 = big((1 << 61) - 1);
+        let m89 = BigUint::from(2u8).pow(89u32) - 1u8;
+        let x = BigUint::from(3u8).pow(90u32) % (&m61 * &m89);
+        assert_eq!(crt(&[(&x % &m61, m61.clone()), (&x % &m89, m89.clone())]), Some((x, &m61 * &m89)));
+    }
+
+    #[test]
+    fn geometric_test() {
+        let big = |x: u64| BigUint::from(x);
+        for (a, n, m) in [(3u64, 1000u64, 1000u64), (10, 12345, 9), (7, 0, 5), (2, 100, u64::MAX)] {
+            assert_eq!(geometric_sum_mod(&big(a), &big(n), &big(m)), big(crate::int64::geometric_sum_mod(a, n, m)));
+        }
+
+        // (a^n - 1) / (a - 1) is exact when a - 1 is invertible
+        let m127 = BigUint::from(2u8).pow(127u32) - 1u8;
+        let (a, n) = (big(12345), BigUint::from(10u8).pow(30u32));
+        let expected = (&a).pow_mod(&n, &m127).sub_mod(&BigUint::one(), &m127).mul_mod(&(&a - 1u8).inv_mod(&m127).unwrap(), &m127);
+        assert_eq!(geometric_sum_mod(&a, &n, &m127), expected);
     }
 }
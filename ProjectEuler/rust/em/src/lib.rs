@@ -1,5 +1,20 @@
+pub mod combinatorics;
+pub mod dirichlet;
+pub mod dlog;
 pub mod int64;
 pub mod intbig;
+pub mod fibonacci;
+pub mod floor;
 pub mod fraction;
+pub mod matrix;
+pub mod modcomb;
+pub mod modint;
+pub mod modroot;
+pub mod partitions;
+pub mod poly;
+pub mod polynomial;
+pub mod powersum;
 pub mod prime;
+pub mod recurrence;
+pub mod series;
 pub mod traits;
@@ -0,0 +1,452 @@
+//! Dense matrices over modular integers, integers and rationals
+//!
+//! Multiplication and exponentiation work for any ring-like element type. Gaussian
+//! elimination is available for the `Field` types (e.g. `Mod<P>` and `BigRational`), and
+//! for `u64` residues modulo a prime through the `_mod` methods. Integer matrices use
+//! fraction-free Bareiss elimination for the determinant.
+
+use std::ops::{Add, Sub, Mul, Div, Index, IndexMut};
+use num_traits::{Zero, One};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::{Ratio, BigRational};
+use crate::modint::Mod;
+use crate::traits::{ModInt, ModUnary};
+
+/// Element types supporting exact division by non-zero values
+pub trait Field: Clone + PartialEq + Zero + One + Sub<Output = Self> + Div<Output = Self> {}
+
+impl<const M: u64> Field for Mod<M> {}
+impl<T: Clone + Integer> Field for Ratio<T> {}
+
+/// A dense matrix stored in row-major order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix<T> {
+    nrows: usize,
+    ncols: usize,
+    data: Vec<T>
+}
+
+impl<T> Matrix<T> {
+    /// Create a matrix from the elements in row-major order
+    pub fn new(nrows: usize, ncols: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), nrows * ncols);
+        Matrix { nrows, ncols, data }
+    }
+
+    /// Create a matrix from a list of rows with the same length
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let nrows = rows.len();
+        let ncols = rows.first().map_or(0, |r| r.len());
+        assert!(rows.iter().all(|r| r.len() == ncols));
+        Matrix { nrows, ncols, data: rows.into_iter().flatten().collect() }
+    }
+
+    #[inline]
+    pub fn nrows(&self) -> usize { self.nrows }
+
+    #[inline]
+    pub fn ncols(&self) -> usize { self.ncols }
+
+    #[inline]
+    pub fn is_square(&self) -> bool { self.nrows == self.ncols }
+
+    /// Returns the i-th row as a slice
+    #[inline]
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.data[i * self.ncols..(i + 1) * self.ncols]
+    }
+
+    pub fn swap_rows(&mut self, i: usize, j: usize) {
+        for k in 0..self.ncols {
+            self.data.swap(i * self.ncols + k, j * self.ncols + k);
+        }
+    }
+
+    /// Convert each of the elements
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Matrix<U> {
+        Matrix { nrows: self.nrows, ncols: self.ncols, data: self.data.iter().map(f).collect() }
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn transpose(&self) -> Self {
+        let data = (0..self.ncols).flat_map(|j| (0..self.nrows).map(move |i| (i, j)))
+            .map(|(i, j)| self[(i, j)].clone()).collect();
+        Matrix { nrows: self.ncols, ncols: self.nrows, data }
+    }
+}
+
+impl<T: Clone + Zero> Matrix<T> {
+    pub fn zeros(nrows: usize, ncols: usize) -> Self {
+        Matrix { nrows, ncols, data: vec![T::zero(); nrows * ncols] }
+    }
+}
+
+impl<T: Clone + Zero + One> Matrix<T> {
+    pub fn identity(n: usize) -> Self {
+        let mut result = Self::zeros(n, n);
+        for i in 0..n {
+            result[(i, i)] = T::one();
+        }
+        result
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.data[i * self.ncols + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    #[inline]
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.data[i * self.ncols + j]
+    }
+}
+
+impl<T: Clone + Add<Output = T>> Add for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn add(self, rhs: &Matrix<T>) -> Matrix<T> {
+        assert!(self.nrows == rhs.nrows && self.ncols == rhs.ncols);
+        let data = self.data.iter().zip(&rhs.data).map(|(a, b)| a.clone() + b.clone()).collect();
+        Matrix { nrows: self.nrows, ncols: self.ncols, data }
+    }
+}
+
+impl<T: Clone + Zero + Mul<Output = T>> Mul for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.ncols, rhs.nrows);
+        let mut result = Matrix::<T>::zeros(self.nrows, rhs.ncols);
+        for i in 0..self.nrows {
+            for k in 0..self.ncols {
+                let a = &self[(i, k)];
+                if a.is_zero() {
+                    continue;
+                }
+                for j in 0..rhs.ncols {
+                    result[(i, j)] = result[(i, j)].clone() + a.clone() * rhs[(k, j)].clone();
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<T: Clone + Zero + One + Mul<Output = T>> Matrix<T> {
+    /// Returns self^exp by binary exponentiation, the matrix has to be square
+    pub fn pow(&self, mut exp: u64) -> Self {
+        assert!(self.is_square());
+        let mut result = Self::identity(self.nrows);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 > 0 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+/// The arithmetic used by the Gaussian elimination
+trait FieldOps<T> {
+    fn zero(&self) -> T;
+    fn one(&self) -> T;
+    fn sub(&self, a: &T, b: &T) -> T;
+    fn mul(&self, a: &T, b: &T) -> T;
+    fn div(&self, a: &T, b: &T) -> T;
+}
+
+struct Natural;
+
+impl<T: Field> FieldOps<T> for Natural {
+    fn zero(&self) -> T { T::zero() }
+    fn one(&self) -> T { T::one() }
+    fn sub(&self, a: &T, b: &T) -> T { a.clone() - b.clone() }
+    fn mul(&self, a: &T, b: &T) -> T { a.clone() * b.clone() }
+    fn div(&self, a: &T, b: &T) -> T { a.clone() / b.clone() }
+}
+
+struct PrimeModulus(u64);
+
+impl FieldOps<u64> for PrimeModulus {
+    fn zero(&self) -> u64 { 0 }
+    fn one(&self) -> u64 { 1 % self.0 }
+    fn sub(&self, a: &u64, b: &u64) -> u64 { a.sub_mod(b, &self.0) }
+    fn mul(&self, a: &u64, b: &u64) -> u64 { a.mul_mod(b, &self.0) }
+    fn div(&self, a: &u64, b: &u64) -> u64 { a.mul_mod(b.inv_mod(&self.0).unwrap(), &self.0) }
+}
+
+/// Transform the matrix into the reduced row echelon form in place. Returns the pivot columns
+/// and the determinant (which is only meaningful for square matrices).
+fn rref_in<T: Clone + PartialEq, F: FieldOps<T>>(f: &F, a: &mut Matrix<T>) -> (Vec<usize>, T) {
+    let zero = f.zero();
+    let mut pivots = Vec::new();
+    let mut det = f.one();
+    for col in 0..a.ncols {
+        let row = pivots.len();
+        if row == a.nrows {
+            break;
+        }
+        let p = match (row..a.nrows).find(|&i| a[(i, col)] != zero) {
+            Some(p) => p, None => continue
+        };
+        if p != row {
+            a.swap_rows(p, row);
+            det = f.sub(&zero, &det);
+        }
+
+        // normalize the pivot row, then eliminate the column in other rows
+        let pivot = a[(row, col)].clone();
+        det = f.mul(&det, &pivot);
+        for j in col..a.ncols {
+            a[(row, j)] = f.div(&a[(row, j)], &pivot);
+        }
+        for i in 0..a.nrows {
+            if i == row || a[(i, col)] == zero {
+                continue;
+            }
+            let factor = a[(i, col)].clone();
+            for j in col..a.ncols {
+                a[(i, j)] = f.sub(&a[(i, j)], &f.mul(&factor, &a[(row, j)]));
+            }
+        }
+        pivots.push(col);
+    }
+    if pivots.len() < a.nrows {
+        det = zero;
+    }
+    (pivots, det)
+}
+
+fn inverse_in<T: Clone + PartialEq, F: FieldOps<T>>(f: &F, a: &Matrix<T>) -> Option<Matrix<T>> {
+    assert!(a.is_square());
+    let n = a.nrows;
+
+    // eliminate [A | I] into [I | A^-1]
+    let mut data = Vec::with_capacity(2 * n * n);
+    for i in 0..n {
+        data.extend_from_slice(a.row(i));
+        data.extend((0..n).map(|j| if i == j { f.one() } else { f.zero() }));
+    }
+    let mut augmented = Matrix::new(n, 2 * n, data);
+    let (pivots, _) = rref_in(f, &mut augmented);
+    if pivots.len() < n || pivots[n - 1] != n - 1 {
+        return None;
+    }
+    let data = (0..n).flat_map(|i| augmented.row(i)[n..].to_vec()).collect();
+    Some(Matrix::new(n, n, data))
+}
+
+fn null_space_in<T: Clone + PartialEq, F: FieldOps<T>>(f: &F, a: &Matrix<T>) -> Vec<Vec<T>> {
+    let mut reduced = a.clone();
+    let (pivots, _) = rref_in(f, &mut reduced);
+
+    // each free column gives a basis vector
+    let mut basis = Vec::new();
+    let mut is_pivot = vec![false; a.ncols];
+    for &p in &pivots {
+        is_pivot[p] = true;
+    }
+    for free in (0..a.ncols).filter(|&j| !is_pivot[j]) {
+        let mut v = vec![f.zero(); a.ncols];
+        v[free] = f.one();
+        for (i, &p) in pivots.iter().enumerate() {
+            v[p] = f.sub(&f.zero(), &reduced[(i, free)]);
+        }
+        basis.push(v);
+    }
+    basis
+}
+
+impl<T: Field> Matrix<T> {
+    /// Returns the reduced row echelon form by Gaussian elimination, with the pivot columns
+    pub fn rref(&self) -> (Self, Vec<usize>) {
+        let mut result = self.clone();
+        let (pivots, _) = rref_in(&Natural, &mut result);
+        (result, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    pub fn det(&self) -> T {
+        assert!(self.is_square());
+        rref_in(&Natural, &mut self.clone()).1
+    }
+
+    /// Returns the inverse matrix, or None if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        inverse_in(&Natural, self)
+    }
+
+    /// Returns a basis of the solutions of A * x = 0
+    pub fn null_space(&self) -> Vec<Vec<T>> {
+        null_space_in(&Natural, self)
+    }
+}
+
+impl Matrix<u64> {
+    /// Returns self * rhs mod m
+    pub fn mul_mod(&self, rhs: &Self, m: u64) -> Self {
+        assert_eq!(self.ncols, rhs.nrows);
+        let mut result = Matrix::<u64>::zeros(self.nrows, rhs.ncols);
+        for i in 0..self.nrows {
+            for k in 0..self.ncols {
+                let a = self[(i, k)];
+                for j in 0..rhs.ncols {
+                    result[(i, j)] = result[(i, j)].add_mod(a.mul_mod(rhs[(k, j)], &m), &m);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns self^exp mod m, the matrix has to be square
+    pub fn pow_mod(&self, mut exp: u64, m: u64) -> Self {
+        assert!(self.is_square());
+        let mut result = Matrix::<u64>::identity(self.nrows).map(|x| x % m);
+        let mut base = self.map(|x| x % m);
+        while exp > 0 {
+            if exp & 1 > 0 {
+                result = result.mul_mod(&base, m);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul_mod(&base, m);
+            }
+        }
+        result
+    }
+
+    /// Returns the reduced row echelon form modulo a prime p, with the pivot columns
+    pub fn rref_mod(&self, p: u64) -> (Self, Vec<usize>) {
+        let mut result = self.map(|x| x % p);
+        let (pivots, _) = rref_in(&PrimeModulus(p), &mut result);
+        (result, pivots)
+    }
+
+    /// Returns the rank modulo a prime p
+    pub fn rank_mod(&self, p: u64) -> usize {
+        self.rref_mod(p).1.len()
+    }
+
+    /// Returns the determinant modulo a prime p
+    pub fn det_mod(&self, p: u64) -> u64 {
+        assert!(self.is_square());
+        rref_in(&PrimeModulus(p), &mut self.map(|x| x % p)).1
+    }
+
+    /// Returns the inverse matrix modulo a prime p, or None if the matrix is singular
+    pub fn inverse_mod(&self, p: u64) -> Option<Self> {
+        inverse_in(&PrimeModulus(p), &self.map(|x| x % p))
+    }
+
+    /// Returns a basis of the solutions of A * x ≡ 0 (mod p)
+    pub fn null_space_mod(&self, p: u64) -> Vec<Vec<u64>> {
+        null_space_in(&PrimeModulus(p), &self.map(|x| x % p))
+    }
+}
+
+impl Matrix<BigInt> {
+    /// Returns the determinant by Bareiss algorithm, all the divisions are exact
+    pub fn det(&self) -> BigInt {
+        assert!(self.is_square());
+        let n = self.nrows;
+        let mut a = self.clone();
+        let mut negative = false;
+        let mut prev = BigInt::one();
+        for k in 0..n {
+            if a[(k, k)].is_zero() {
+                match (k + 1..n).find(|&i| !a[(i, k)].is_zero()) {
+                    Some(i) => { a.swap_rows(i, k); negative = !negative; },
+                    None => return BigInt::zero()
+                }
+            }
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    a[(i, j)] = (&a[(i, j)] * &a[(k, k)] - &a[(i, k)] * &a[(k, j)]) / &prev;
+                }
+            }
+            prev = a[(k, k)].clone();
+        }
+        if negative { -prev } else { prev }
+    }
+
+    /// Returns the rank, which is computed over the rationals
+    pub fn rank(&self) -> usize {
+        self.map(|x| BigRational::from_integer(x.clone())).rank()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type M7 = Mod<1000000007>;
+
+    #[test]
+    fn multiply_test() {
+        let a = Matrix::from_rows(vec![vec![1u64, 1], vec![1, 0]]);
+        assert_eq!(a.pow_mod(10, 1000), Matrix::from_rows(vec![vec![89, 55], vec![55, 34]]));
+        assert_eq!(a.pow_mod(0, 1), Matrix::zeros(2, 2));
+
+        let b = a.map(|&x| M7::from(x)).pow(90);
+        assert_eq!(b[(0, 1)].value(), crate::fibonacci::fibonacci_mod(90, 1000000007));
+        let c = a.map(|&x| BigInt::from(x)).pow(90);
+        assert_eq!(c[(0, 1)], BigInt::from(2880067194370816120u64));
+
+        let d = Matrix::new(2, 3, vec![1i64, 2, 3, 4, 5, 6]);
+        assert_eq!(&d * &d.transpose(), Matrix::new(2, 2, vec![14, 32, 32, 77]));
+        assert_eq!(&d + &d, d.map(|x| 2 * x));
+    }
+
+    #[test]
+    fn elimination_test() {
+        let p = 1000000007;
+        let a = Matrix::from_rows(vec![vec![2u64, 3, 1], vec![4, 1, 5], vec![6, 4, 6]]);
+        assert_eq!(a.det_mod(p), 0);
+        assert_eq!(a.rank_mod(p), 2);
+        assert!(a.inverse_mod(p).is_none());
+        let basis = a.null_space_mod(p);
+        assert_eq!(basis.len(), 1);
+        let x = Matrix::new(3, 1, basis[0].clone());
+        assert_eq!(a.mul_mod(&x, p), Matrix::zeros(3, 1));
+
+        let b = Matrix::from_rows(vec![vec![2u64, 3, 1], vec![4, 1, 5], vec![6, 4, 7]]);
+        let inv = b.inverse_mod(p).unwrap();
+        assert_eq!(b.mul_mod(&inv, p), Matrix::identity(3));
+        assert_eq!(b.det_mod(p), p - 10);
+        assert!(b.null_space_mod(p).is_empty());
+
+        // the same matrix over rationals and integers
+        let q = b.map(|&x| BigRational::from_integer(BigInt::from(x)));
+        assert_eq!(q.det(), BigRational::from_integer(BigInt::from(-10)));
+        assert_eq!(&q * &q.inverse().unwrap(), Matrix::identity(3));
+        assert_eq!(b.map(|&x| BigInt::from(x)).det(), BigInt::from(-10));
+        assert_eq!(a.map(|&x| BigInt::from(x)).det(), BigInt::zero());
+        assert_eq!(a.map(|&x| BigInt::from(x)).rank(), 2);
+        assert_eq!(b.map(|&x| M7::from(x)).det(), M7::from(p - 10));
+
+        // Bareiss agrees with the elimination modulo p
+        let h = Matrix::new(5, 5, (0..25u64).map(|i| (i * i * 7 + 3 * i + 11) % 23).collect());
+        let det = h.map(|&x| BigInt::from(x)).det();
+        assert_eq!(det.mod_floor(&BigInt::from(p)), BigInt::from(h.det_mod(p)));
+
+        let (r, pivots) = Matrix::new(2, 4, vec![0u64, 2, 4, 6, 0, 1, 2, 4]).rref_mod(p);
+        assert_eq!(pivots, [1, 3]);
+        assert_eq!(r, Matrix::new(2, 4, vec![0, 1, 2, 0, 0, 0, 0, 1]));
+    }
+}
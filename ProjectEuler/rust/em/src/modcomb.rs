@@ -0,0 +1,373 @@
+//! Factorials and binomial coefficients in modular arithmetic
+
+use std::collections::HashMap;
+use crate::int64::crt;
+use crate::traits::{ModInt, ModUnary};
+
+/// Table of factorials and their inverses modulo a prime p, for numbers up to a limit below p
+pub struct FactorialTable {
+    p: u64,
+    fact: Vec<u64>,
+    inv_fact: Vec<u64>
+}
+
+impl FactorialTable {
+    /// Create the table of 0!, 1!, ..., limit! modulo p, p has to be a prime larger than limit
+    pub fn new(limit: usize, p: u64) -> Self {
+        assert!((limit as u64) < p);
+        let mut fact = vec![1 % p; limit + 1];
+        for i in 1..=limit {
+            fact[i] = fact[i - 1].mul_mod(i as u64, &p);
+        }
+
+        // inverses are computed backwards from the last one
+        let mut inv_fact = vec![0; limit + 1];
+        inv_fact[limit] = fact[limit].inv_mod(&p).unwrap();
+        for i in (1..=limit).rev() {
+            inv_fact[i - 1] = inv_fact[i].mul_mod(i as u64, &p);
+        }
+        FactorialTable { p, fact, inv_fact }
+    }
+
+    /// Returns n! mod p
+    #[inline]
+    pub fn factorial(&self, n: usize) -> u64 {
+        self.fact[n]
+    }
+
+    /// Returns (n!)^-1 mod p
+    #[inline]
+    pub fn inv_factorial(&self, n: usize) -> u64 {
+        self.inv_fact[n]
+    }
+
+    /// Returns C(n, k) mod p, n has to be within the table
+    pub fn binomial(&self, n: usize, k: usize) -> u64 {
+        if k > n {
+            return 0;
+        }
+        self.fact[n].mul_mod(self.inv_fact[k], &self.p).mul_mod(self.inv_fact[n - k], &self.p)
+    }
+
+    /// Returns C(n, k) mod p for arbitrary n by Lucas' theorem, the table has to cover
+    /// all the numbers below p.
+    pub fn lucas(&self, mut n: u64, mut k: u64) -> u64 {
+        assert_eq!(self.fact.len() as u64, self.p);
+        let mut result = 1 % self.p;
+        while k > 0 {
+            let (ni, ki) = (n % self.p, k % self.p);
+            if ki > ni {
+                return 0;
+            }
+            result = result.mul_mod(self.binomial(ni as usize, ki as usize), &self.p);
+            n /= self.p;
+            k /= self.p;
+        }
+        result
+    }
+}
+
+/// The largest prime power p^e that `PrimePowerBinomial` builds a table for. A table of
+/// this size takes 32 MB.
+pub const BINOMIAL_TABLE_LIMIT: u64 = 1 << 22;
+
+/// The products of the integers in [1, r] not divisible by p, modulo p^e
+enum UnitProducts {
+    Table(Vec<u64>), // the products for all r < p^e
+    Blocks(Vec<u64>) // coefficients of F(x) = (x + 1)(x + 2)...(x + p - 1) below x^e
+}
+
+/// Binomial coefficients modulo a prime power p^e by Granville's generalization of
+/// Lucas' theorem.
+///
+/// Up to `BINOMIAL_TABLE_LIMIT` a table of size p^e is built, and each query takes
+/// O(log n) time. Above it, the product over each block [kp + 1, kp + p - 1] is F(kp),
+/// where only the terms of F below x^e matter since p^e | (kp)^e. Then it takes O(p * e)
+/// time to create, and O(e * p^(e-1) + p) time for each base p digit of a query.
+pub struct PrimePowerBinomial {
+    p: u64,
+    e: u32,
+    pe: u64,
+    units: UnitProducts
+}
+
+impl PrimePowerBinomial {
+    pub fn new(p: u64, e: u32) -> Self {
+        let pe = p.checked_pow(e).expect("the prime power overflows u64");
+        Self::build(p, e, pe <= BINOMIAL_TABLE_LIMIT)
+    }
+
+    fn build(p: u64, e: u32, table: bool) -> Self {
+        let pe = p.pow(e);
+        let units = if table {
+            let mut units = vec![1 % pe; pe as usize];
+            for j in 1..pe {
+                let term = if j.is_multiple_of(p) { 1 } else { j };
+                units[j as usize] = units[j as usize - 1].mul_mod(term, &pe);
+            }
+            UnitProducts::Table(units)
+        } else {
+            let mut coeffs = vec![0; e as usize];
+            coeffs[0] = 1 % pe;
+            for j in 1..p {
+                for i in (0..e as usize).rev() {
+                    let lower = if i > 0 { coeffs[i - 1] } else { 0 };
+                    coeffs[i] = coeffs[i].mul_mod(j, &pe).add_mod(lower, &pe);
+                }
+            }
+            UnitProducts::Blocks(coeffs)
+        };
+        PrimePowerBinomial { p, e, pe, units }
+    }
+
+    /// Returns the product of j <= r with p ∤ j modulo p^e, r has to be below p^e
+    fn unit_product(&self, r: u64) -> u64 {
+        let (p, pe) = (self.p, self.pe);
+        match &self.units {
+            UnitProducts::Table(units) => units[r as usize],
+            UnitProducts::Blocks(coeffs) => {
+                let (blocks, rest) = (r / p, r % p);
+                let mut result = 1 % pe;
+                for k in 0..blocks {
+                    let x = k * p;
+                    let f = coeffs.iter().rev().fold(0, |acc, &c| acc.mul_mod(x, &pe).add_mod(c, &pe));
+                    result = result.mul_mod(f, &pe);
+                }
+                (1..=rest).fold(result, |acc, j| acc.mul_mod(blocks * p + j, &pe))
+            }
+        }
+    }
+
+    /// Returns n! with all factors of p removed, modulo p^e
+    pub fn factorial_unit(&self, mut n: u64) -> u64 {
+        // the product over a full period [1, p^e) is -1, except that it's 1 for 2^e with e >= 3
+        let period = if self.p == 2 && self.e >= 3 { 1 } else { self.pe - 1 };
+        let mut result = 1 % self.pe;
+        while n > 0 {
+            let part = period.pow_mod(n / self.pe, &self.pe).mul_mod(self.unit_product(n % self.pe), &self.pe);
+            result = result.mul_mod(part, &self.pe);
+            n /= self.p;
+        }
+        result
+    }
+
+    /// Returns C(n, k) mod p^e
+    pub fn binomial(&self, n: u64, k: u64) -> u64 {
+        if k > n {
+            return 0;
+        }
+
+        // the power of p in C(n, k) equals the number of carries when adding k and n-k in base p
+        let v = factorial_valuation(n, self.p) - factorial_valuation(k, self.p) - factorial_valuation(n - k, self.p);
+        if v >= self.e as u64 {
+            return 0;
+        }
+
+        let denom = self.factorial_unit(k).mul_mod(self.factorial_unit(n - k), &self.pe);
+        self.p.pow(v as u32).mul_mod(self.factorial_unit(n), &self.pe)
+            .mul_mod(denom.inv_mod(&self.pe).unwrap(), &self.pe)
+    }
+}
+
+enum BinomialPart {
+    Table(PrimePowerBinomial),
+    Prime(u64) // a prime above the table limit, by `binomial_mod_prime`
+}
+
+/// Binomial coefficients modulo an arbitrary m, combined by CRT from the results modulo
+/// each prime power factor of m
+pub struct BinomialMod {
+    parts: Vec<BinomialPart>
+}
+
+impl BinomialMod {
+    /// Create the tables with the factorization of m. The prime factors above
+    /// `BINOMIAL_TABLE_LIMIT` with exponent 1 are handled by `binomial_mod_prime`, and the
+    /// other prime powers above it by the block products of `PrimePowerBinomial`.
+    pub fn new(factors: &HashMap<u64, usize>) -> Self {
+        let parts = factors.iter().map(|(&p, &e)| {
+            if e == 1 && p > BINOMIAL_TABLE_LIMIT {
+                BinomialPart::Prime(p)
+            } else {
+                BinomialPart::Table(PrimePowerBinomial::new(p, e as u32))
+            }
+        }).collect();
+        BinomialMod { parts }
+    }
+
+    /// Returns C(n, k) mod m
+    pub fn binomial(&self, n: u64, k: u64) -> u64 {
+        let congruences: Vec<(u64, u64)> = self.parts.iter().map(|part| match part {
+            BinomialPart::Table(table) => (table.binomial(n, k), table.pe),
+            BinomialPart::Prime(p) => (binomial_mod_prime(n, k, *p), *p)
+        }).collect();
+        crt(&congruences).unwrap().0
+    }
+}
+
+/// Returns C(n, k) mod p without tables, p has to be a prime. By Lucas' theorem, it takes
+/// O(min(k_i, n_i - k_i)) time for each pair of digits n_i, k_i in base p.
+pub fn binomial_mod_prime(mut n: u64, mut k: u64, p: u64) -> u64 {
+    let mut result = 1 % p;
+    while k > 0 {
+        let (ni, ki) = (n % p, k % p);
+        if ki > ni {
+            return 0;
+        }
+
+        // C(ni, ki) = ni * (ni - 1) * ... * (ni - ki + 1) / ki!
+        let ki = ki.min(ni - ki);
+        let (num, den) = (0..ki).fold((1 % p, 1 % p), |(num, den), i| {
+            (num.mul_mod(ni - i, &p), den.mul_mod(i + 1, &p))
+        });
+        result = result.mul_mod(num, &p).mul_mod(den.inv_mod(&p).unwrap(), &p);
+        n /= p;
+        k /= p;
+    }
+    result
+}
+
+/// Returns n! mod p, p has to be a prime. It takes O(min(n, p - n)) time by Wilson's theorem.
+pub fn factorial_mod_prime(n: u64, p: u64) -> u64 {
+    if n >= p {
+        return 0;
+    }
+
+    // n! * (p-1-n)! ≡ (-1)^(n+1) (mod p)
+    let m = p - 1 - n;
+    if n <= m {
+        return (1..=n).fold(1 % p, |acc, i| acc.mul_mod(i, &p));
+    }
+    let rest = (1..=m).fold(1 % p, |acc, i| acc.mul_mod(i, &p)).inv_mod(&p).unwrap();
+    if n % 2 == 1 { rest } else { rest.neg_mod(&p) }
+}
+
+/// Returns the exponent of a prime p in n! by Legendre's formula
+pub fn factorial_valuation(mut n: u64, p: u64) -> u64 {
+    let mut v = 0;
+    while n > 0 {
+        n /= p;
+        v += n;
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::PrimeBuffer;
+
+    /// Pascal's triangle modulo m
+    fn pascal(rows: usize, m: u64) -> Vec<Vec<u64>> {
+        let mut table = vec![vec![1 % m]];
+        for n in 1..rows {
+            let prev = &table[n - 1];
+            let row = (0..=n).map(|k| {
+                let left = if k > 0 { prev[k - 1] } else { 0 };
+                let right = if k < n { prev[k] } else { 0 };
+                left.add_mod(right, &m)
+            }).collect();
+            table.push(row);
+        }
+        table
+    }
+
+    #[test]
+    fn factorial_test() {
+        for &p in &[2u64, 3, 13, 1009] {
+            let mut fact = 1 % p;
+            for n in 0..p + 3 {
+                if n > 0 {
+                    fact = fact.mul_mod(n, &p);
+                }
+                assert_eq!(factorial_mod_prime(n, p), fact);
+
+                let valuation: u64 = (1..=n).map(|mut i| {
+                    let mut v = 0;
+                    while i % p == 0 { i /= p; v += 1; }
+                    v
+                }).sum();
+                assert_eq!(factorial_valuation(n, p), valuation);
+            }
+        }
+
+        let table = FactorialTable::new(1000, 1000000007);
+        assert_eq!(table.factorial(1000), factorial_mod_prime(1000, 1000000007));
+        assert_eq!(table.factorial(1000).mul_mod(table.inv_factorial(1000), &1000000007), 1);
+        assert_eq!(factorial_mod_prime(1000000000, 1000000007), 698611116);
+    }
+
+    #[test]
+    fn binomial_test() {
+        let rows = 120;
+        let mut pb = PrimeBuffer::new();
+        for m in 2..=100u64 {
+            let expected = pascal(rows, m);
+            let binomial = BinomialMod::new(&pb.factors(m));
+            for n in 0..rows {
+                for k in 0..=n + 1 {
+                    let expected = if k > n { 0 } else { expected[n][k] };
+                    assert_eq!(binomial.binomial(n as u64, k as u64), expected);
+                }
+            }
+        }
+
+        // Lucas' theorem agrees with Granville's one when e = 1
+        for &p in &[2u64, 3, 7, 101] {
+            let table = FactorialTable::new(p as usize - 1, p);
+            let granville = PrimePowerBinomial::new(p, 1);
+            let n = 1000000000000000000u64;
+            for k in [0, 1, 12345, 500000000000000000, n - 1, n, n + 1] {
+                assert_eq!(table.lucas(n, k), granville.binomial(n, k));
+            }
+        }
+
+        // C(10^18, 10^9) mod 2^10 * 3^5 * 5^3
+        let binomial = BinomialMod::new(&HashMap::from([(2, 10), (3, 5), (5, 3)]));
+        let (n, k) = (1000000000000000000u64, 1000000000u64);
+        let c = binomial.binomial(n, k);
+        assert!(c < 1024 * 243 * 125);
+        assert_eq!(c % 1024, PrimePowerBinomial::new(2, 10).binomial(n, k));
+        assert_eq!(c % 243, PrimePowerBinomial::new(3, 5).binomial(n, k));
+        assert_eq!(c % 125, PrimePowerBinomial::new(5, 3).binomial(n, k));
+
+        // large primes don't get a table, digits below p are multiplied directly
+        let p = 1000000007u64;
+        let table = FactorialTable::new(1000000, p);
+        for (n, k) in [(10, 3), (1000000, 500000), (999999, 12345), (5, 7)] {
+            assert_eq!(binomial_mod_prime(n, k, p), table.binomial(n as usize, k as usize));
+        }
+        assert_eq!(binomial_mod_prime(p + 5, 3, p), 10);
+        assert_eq!(binomial_mod_prime(3 * p + 10, p + 4, p), 630);
+        assert_eq!(binomial_mod_prime(p + 3, 5, p), 0);
+
+        // m = 2^10 * 3^5 * (10^9 + 7) and (10^9 + 7) * 998244353
+        let (n, k) = (1000000u64, 500000u64);
+        let c = BinomialMod::new(&HashMap::from([(2, 10), (3, 5), (p, 1)])).binomial(n, k);
+        assert_eq!(c % 1024, PrimePowerBinomial::new(2, 10).binomial(n, k));
+        assert_eq!(c % 243, PrimePowerBinomial::new(3, 5).binomial(n, k));
+        assert_eq!(c % p, table.binomial(n as usize, k as usize));
+        let q = 998244353u64;
+        let c = BinomialMod::new(&HashMap::from([(p, 1), (q, 1)])).binomial(n, k);
+        assert_eq!(c % p, table.binomial(n as usize, k as usize));
+        assert_eq!(c % q, FactorialTable::new(n as usize, q).binomial(n as usize, k as usize));
+
+        // block products agree with the tables
+        for &(p, e) in &[(2u64, 1u32), (2, 2), (2, 5), (3, 4), (5, 3), (7, 2), (13, 1)] {
+            let (table, blocks) = (PrimePowerBinomial::build(p, e, true), PrimePowerBinomial::build(p, e, false));
+            for n in (0..200).chain([123456789, 1000000000000000000]) {
+                for k in [0, 1, 2, 7, n / 3, n / 2, n] {
+                    assert_eq!(table.binomial(n, k), blocks.binomial(n, k));
+                }
+            }
+        }
+
+        // prime powers above the table limit
+        let binomial = BinomialMod::new(&HashMap::from([(10007, 2)]));
+        assert_eq!(binomial.binomial(10, 3), 120);
+        assert_eq!(binomial.binomial(1000000, 12345), 70699938);
+        assert_eq!(binomial.binomial(1000000000000, 3), 59181604);
+        let binomial = PrimePowerBinomial::new(1009, 3);
+        assert_eq!(binomial.binomial(1000000, 500000), 781815578);
+    }
+}
@@ -0,0 +1,410 @@
+//! Integers in modular arithmetic with operator overloading
+
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign, MulAssign, DivAssign};
+use std::iter::{Sum, Product};
+use std::fmt;
+use num_traits::{Pow, Zero, One};
+use crate::traits::{ModInt, ModUnary};
+
+/// An integer modulo a compile-time constant M
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Mod<const M: u64>(u64);
+
+impl<const M: u64> Mod<M> {
+    pub const MODULUS: u64 = M;
+
+    #[inline]
+    pub const fn new(value: u64) -> Self {
+        Mod(value % M)
+    }
+
+    /// Create from a signed integer, the value is taken as the non-negative residue
+    #[inline]
+    pub const fn from_i64(value: i64) -> Self {
+        Mod((value as i128).rem_euclid(M as i128) as u64)
+    }
+
+    /// Return the residue in [0, M)
+    #[inline]
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Return the multiplicative inverse, or None if the value is not coprime to M
+    #[inline]
+    pub fn inv(self) -> Option<Self> {
+        self.0.inv_mod(&M).map(Mod)
+    }
+}
+
+impl<const M: u64> From<u64> for Mod<M> {
+    #[inline]
+    fn from(value: u64) -> Self { Mod::new(value) }
+}
+
+impl<const M: u64> From<Mod<M>> for u64 {
+    #[inline]
+    fn from(value: Mod<M>) -> u64 { value.0 }
+}
+
+impl<const M: u64> Zero for Mod<M> {
+    #[inline]
+    fn zero() -> Self { Mod(0) }
+    #[inline]
+    fn is_zero(&self) -> bool { self.0 == 0 }
+}
+
+impl<const M: u64> One for Mod<M> {
+    #[inline]
+    fn one() -> Self { Mod::new(1) }
+}
+
+impl<const M: u64> fmt::Display for Mod<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const M: u64> Add for Mod<M> {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self { Mod(self.0.add_mod(rhs.0, &M)) }
+}
+
+impl<const M: u64> Sub for Mod<M> {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self { Mod(self.0.sub_mod(rhs.0, &M)) }
+}
+
+impl<const M: u64> Mul for Mod<M> {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: Self) -> Self { Mod(self.0.mul_mod(rhs.0, &M)) }
+}
+
+impl<const M: u64> Div for Mod<M> {
+    type Output = Self;
+
+    /// Panics if rhs is not invertible
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        let inv = rhs.inv().expect("the divisor is not invertible");
+        Mod(self.0.mul_mod(inv.0, &M))
+    }
+}
+
+impl<const M: u64> Neg for Mod<M> {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self { Mod(self.0.neg_mod(&M)) }
+}
+
+impl<const M: u64> Pow<u64> for Mod<M> {
+    type Output = Self;
+    #[inline]
+    fn pow(self, exp: u64) -> Self { Mod(self.0.pow_mod(exp, &M)) }
+}
+
+impl<const M: u64> Sum for Mod<M> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Mod::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<'a, const M: u64> Sum<&'a Mod<M>> for Mod<M> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Mod::zero(), |acc, x| acc + *x)
+    }
+}
+
+impl<const M: u64> Product for Mod<M> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Mod::one(), |acc, x| acc * x)
+    }
+}
+
+impl<'a, const M: u64> Product<&'a Mod<M>> for Mod<M> {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Mod::one(), |acc, x| acc * *x)
+    }
+}
+
+/// An integer modulo a modulus determined at runtime. Operations between two values
+/// require them to have the same modulus.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DynMod {
+    value: u64,
+    modulus: u64
+}
+
+impl DynMod {
+    #[inline]
+    pub fn new(value: u64, modulus: u64) -> Self {
+        assert!(modulus > 0);
+        DynMod { value: value % modulus, modulus }
+    }
+
+    /// Create from a signed integer, the value is taken as the non-negative residue
+    #[inline]
+    pub fn from_i64(value: i64, modulus: u64) -> Self {
+        assert!(modulus > 0);
+        DynMod { value: (value as i128).rem_euclid(modulus as i128) as u64, modulus }
+    }
+
+    /// Return the residue in [0, modulus)
+    #[inline]
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    #[inline]
+    pub fn modulus(self) -> u64 {
+        self.modulus
+    }
+
+    /// Return the multiplicative inverse, or None if the value is not coprime to the modulus
+    #[inline]
+    pub fn inv(self) -> Option<Self> {
+        self.value.inv_mod(&self.modulus).map(|value| DynMod { value, modulus: self.modulus })
+    }
+
+    #[inline]
+    fn check(self, rhs: Self) {
+        assert_eq!(self.modulus, rhs.modulus, "the moduli of the operands are different");
+    }
+}
+
+impl fmt::Display for DynMod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Add for DynMod {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        self.check(rhs);
+        DynMod { value: self.value.add_mod(rhs.value, &self.modulus), modulus: self.modulus }
+    }
+}
+
+impl Sub for DynMod {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        self.check(rhs);
+        DynMod { value: self.value.sub_mod(rhs.value, &self.modulus), modulus: self.modulus }
+    }
+}
+
+impl Mul for DynMod {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.check(rhs);
+        DynMod { value: self.value.mul_mod(rhs.value, &self.modulus), modulus: self.modulus }
+    }
+}
+
+impl Div for DynMod {
+    type Output = Self;
+
+    /// Panics if rhs is not invertible
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        self.check(rhs);
+        let inv = rhs.inv().expect("the divisor is not invertible");
+        DynMod { value: self.value.mul_mod(inv.value, &self.modulus), modulus: self.modulus }
+    }
+}
+
+impl Neg for DynMod {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        DynMod { value: self.value.neg_mod(&self.modulus), modulus: self.modulus }
+    }
+}
+
+impl Pow<u64> for DynMod {
+    type Output = Self;
+
+    fn pow(self, exp: u64) -> Self {
+        // Montgomery multiplication avoids the 128-bit divisions for odd moduli
+        let value = if self.modulus & 1 == 1 && self.modulus > 1 {
+            Montgomery::new(self.modulus).pow_mod(self.value, exp)
+        } else {
+            self.value.pow_mod(exp, &self.modulus)
+        };
+        DynMod { value, modulus: self.modulus }
+    }
+}
+
+impl Product for DynMod {
+    /// Panics if the iterator is empty, since the modulus is unknown
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc * x).expect("cannot take the product of an empty iterator")
+    }
+}
+
+impl Sum for DynMod {
+    /// Panics if the iterator is empty, since the modulus is unknown
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc + x).expect("cannot take the sum of an empty iterator")
+    }
+}
+
+macro_rules! impl_assign_ops {
+    ($t:ty $(, $g:tt)?) => {
+        impl$(<const $g: u64>)? AddAssign for $t {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) { *self = *self + rhs }
+        }
+        impl$(<const $g: u64>)? SubAssign for $t {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) { *self = *self - rhs }
+        }
+        impl$(<const $g: u64>)? MulAssign for $t {
+            #[inline]
+            fn mul_assign(&mut self, rhs: Self) { *self = *self * rhs }
+        }
+        impl$(<const $g: u64>)? DivAssign for $t {
+            #[inline]
+            fn div_assign(&mut self, rhs: Self) { *self = *self / rhs }
+        }
+    };
+}
+impl_assign_ops!(Mod<M>, M);
+impl_assign_ops!(DynMod);
+
+/// Montgomery form of the multiplication modulo an odd modulus, which replaces the
+/// division in each multiplication by shifts and multiplications.
+#[derive(Clone, Copy, Debug)]
+pub struct Montgomery {
+    m: u64,
+    m_inv: u64, // -m^-1 mod 2^64
+    r2: u64 // 2^128 mod m
+}
+
+impl Montgomery {
+    pub fn new(m: u64) -> Self {
+        assert!(m & 1 == 1, "Montgomery form requires an odd modulus");
+
+        // Newton's iteration for m^-1 mod 2^64
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(inv)));
+        }
+        let r = ((1u128 << 64) % m as u128) as u64;
+        let r2 = r.mul_mod(r, &m);
+        Montgomery { m, m_inv: inv.wrapping_neg(), r2 }
+    }
+
+    /// Return x*2^-64 mod m for x < m*2^64
+    #[inline]
+    fn reduce(&self, x: u128) -> u64 {
+        let t = (x as u64).wrapping_mul(self.m_inv);
+        let sum = (x >> 64) + (((t as u128) * (self.m as u128)) >> 64) + (((x as u64) != 0) as u128);
+        let result = sum as u64;
+        if sum >= self.m as u128 { (sum - self.m as u128) as u64 } else { result }
+    }
+
+    /// Convert a residue into Montgomery form
+    #[inline]
+    pub fn to_form(&self, x: u64) -> u64 {
+        self.reduce((x % self.m) as u128 * self.r2 as u128)
+    }
+
+    /// Convert a value in Montgomery form back to the residue
+    #[inline]
+    pub fn from_form(&self, x: u64) -> u64 {
+        self.reduce(x as u128)
+    }
+
+    /// Multiply two values in Montgomery form
+    #[inline]
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        self.reduce(a as u128 * b as u128)
+    }
+
+    /// Return (base ^ exp) % m, the input and output are plain residues
+    pub fn pow_mod(&self, base: u64, exp: u64) -> u64 {
+        let mut multi = self.to_form(base);
+        let mut result = self.to_form(1);
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 > 0 {
+                result = self.mul(result, multi);
+            }
+            multi = self.mul(multi, multi);
+            exp >>= 1;
+        }
+        self.from_form(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: u64 = 1_000_000_007;
+    type F = Mod<P>;
+
+    #[test]
+    fn const_mod_test() {
+        let a = F::new(123456789);
+        let b = F::from_i64(-987654321);
+        assert_eq!(b.value(), P - 987654321);
+        assert_eq!((a + b).value(), (123456789 + P - 987654321) % P);
+        assert_eq!((a - b).value(), (123456789 + 987654321) % P);
+        assert_eq!((a * b).value(), 123456789u64.mul_mod(P - 987654321, &P));
+        assert_eq!(a / b * b, a);
+        assert_eq!(-a + a, F::new(0));
+        assert_eq!(a.pow(P - 1), F::new(1));
+        assert_eq!(a.inv().unwrap() * a, F::new(1));
+        assert_eq!(Mod::<12>::new(4).inv(), None);
+
+        let v: Vec<F> = (1..=20).map(F::new).collect();
+        assert_eq!(v.iter().sum::<F>(), F::new(210));
+        assert_eq!(v.iter().product::<F>(), F::new(2432902008176640000 % P));
+
+        let mut c = a;
+        c += b; c -= b; c *= b; c /= b;
+        assert_eq!(c, a);
+        assert_eq!(format!("{}", F::new(P + 5)), "5");
+
+        // modulus above 2^63
+        type G = Mod<{ u64::MAX - 58 }>;
+        assert_eq!(G::new(u64::MAX - 59) + G::new(2), G::new(1));
+        assert_eq!((G::new(u64::MAX - 59) * G::new(u64::MAX - 59)).value(), 1);
+    }
+
+    #[test]
+    fn dyn_mod_test() {
+        let m = 998244353;
+        let a = DynMod::new(31415926535, m);
+        let b = DynMod::from_i64(-27182818284, m);
+        assert_eq!((a + b).value(), (31415926535 % m + m - 27182818284 % m) % m);
+        assert_eq!(a / b * b, a);
+        assert_eq!(-a + a, DynMod::new(0, m));
+        assert_eq!(a.pow(m - 1), DynMod::new(1, m));
+        assert_eq!(a.pow(u64::MAX), DynMod::new(a.value().pow_mod(u64::MAX, &m), m));
+        assert_eq!(vec![a, b, a].into_iter().product::<DynMod>(), a * b * a);
+        assert_eq!(DynMod::new(4, 12).inv(), None);
+    }
+
+    #[test]
+    fn montgomery_test() {
+        for &m in &[3u64, 998244353, (1 << 61) - 1, u64::MAX - 58, u64::MAX] {
+            let mont = Montgomery::new(m);
+            for &(a, b) in &[(0u64, 5u64), (2, 10), (12345678901234, 98765432109876), (u64::MAX, u64::MAX - 1)] {
+                let (fa, fb) = (mont.to_form(a), mont.to_form(b));
+                assert_eq!(mont.from_form(fa), a % m);
+                assert_eq!(mont.from_form(mont.mul(fa, fb)), a.mul_mod(b, &m));
+                assert_eq!(mont.pow_mod(a, b), a.pow_mod(b, &m));
+            }
+        }
+    }
+}
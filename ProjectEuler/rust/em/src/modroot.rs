@@ -0,0 +1,415 @@
+//! Square roots and k-th roots in modular arithmetic
+
+use std::collections::HashMap;
+use num_integer::Integer;
+use crate::traits::{ModInt, ModUnary};
+
+/// Returns a square root of a modulo a prime p, or None if a is not a quadratic residue.
+/// The other root is p minus the returned one.
+///
+/// Tonelli-Shanks algorithm is used when p - 1 has few factors of two, otherwise
+/// Cipolla's algorithm is used.
+pub fn sqrt_mod_prime(a: u64, p: u64) -> Option<u64> {
+    let a = a % p;
+    if a == 0 || p == 2 {
+        return Some(a);
+    }
+    if a.pow_mod((p - 1) / 2, &p) != 1 {
+        return None;
+    }
+    if p % 4 == 3 {
+        return Some(a.pow_mod((p + 1) / 4, &p));
+    }
+
+    const TONELLI_THRESHOLD: u32 = 8;
+    if (p - 1).trailing_zeros() <= TONELLI_THRESHOLD {
+        Some(tonelli_shanks(a, p))
+    } else {
+        Some(cipolla(a, p))
+    }
+}
+
+fn tonelli_shanks(a: u64, p: u64) -> u64 {
+    // find 2^s*q = p - 1 and a non-residue z
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    let mut z = 2;
+    while z.pow_mod((p - 1) / 2, &p) != p - 1 {
+        z += 1;
+    }
+
+    let mut m = s;
+    let mut c = z.pow_mod(q, &p);
+    let mut t = a.pow_mod(q, &p);
+    let mut r = a.pow_mod(q.div_ceil(2), &p);
+    while t != 1 {
+        let mut i = 0;
+        let mut t2i = t;
+        while t2i != 1 {
+            t2i = t2i.mul_mod(t2i, &p);
+            i += 1;
+        }
+        let b = c.pow_mod(1u64 << (m - i - 1), &p);
+        r = r.mul_mod(b, &p);
+        c = b.mul_mod(b, &p);
+        t = t.mul_mod(c, &p);
+        m = i;
+    }
+    r
+}
+
+fn cipolla(a: u64, p: u64) -> u64 {
+    // find t such that t^2 - a is a non-residue w
+    let mut t = 1;
+    let w = loop {
+        let w = t.mul_mod(t, &p).sub_mod(a, &p);
+        if w.pow_mod((p - 1) / 2, &p) == p - 1 {
+            break w;
+        }
+        t += 1;
+    };
+
+    // (t + sqrt(w))^((p+1)/2) in F_p[sqrt(w)]
+    let mul = |(x1, y1): (u64, u64), (x2, y2): (u64, u64)| (
+        x1.mul_mod(x2, &p).add_mod(y1.mul_mod(y2, &p).mul_mod(w, &p), &p),
+        x1.mul_mod(y2, &p).add_mod(x2.mul_mod(y1, &p), &p)
+    );
+    let mut result = (1, 0);
+    let mut multi = (t, 1);
+    let mut exp = p.div_ceil(2);
+    while exp > 0 {
+        if exp & 1 > 0 {
+            result = mul(result, multi);
+        }
+        multi = mul(multi, multi);
+        exp >>= 1;
+    }
+    result.0
+}
+
+/// A set of residues x = base + j * step for j in 0..count, where the bases are smaller
+/// than step. The elements are generated on demand, since the square roots modulo p^e can
+/// be as many as sqrt(p^e).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootSet {
+    bases: Vec<u64>,
+    step: u64,
+    count: u64
+}
+
+impl RootSet {
+    pub fn new(mut bases: Vec<u64>, step: u64, count: u64) -> Self {
+        bases.sort_unstable();
+        debug_assert!(bases.last().is_none_or(|&b| count <= 1 || b < step));
+        RootSet { bases, step, count }
+    }
+
+    /// Returns the number of elements
+    #[inline]
+    pub fn len(&self) -> u64 { self.bases.len() as u64 * self.count }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the i-th smallest element
+    pub fn get(&self, i: u64) -> u64 {
+        let size = self.bases.len() as u64;
+        self.bases[(i % size) as usize] + i / size * self.step
+    }
+
+    /// Returns an iterator over the elements in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+}
+
+/// Returns all square roots of a modulo p^e, p has to be a prime.
+///
+/// The number of roots can be as large as sqrt(p^e) when a ≡ 0 (mod p^e), so they are
+/// returned as a `RootSet` instead of a list.
+pub fn sqrt_mod_prime_power(a: u64, p: u64, e: u32) -> RootSet {
+    let pe = p.pow(e);
+    let a = a % pe;
+    if a == 0 { // x ≡ 0 (mod p^ceil(e/2))
+        return RootSet::new(vec![0], p.pow(e.div_ceil(2)), p.pow(e / 2));
+    }
+
+    // x = p^(v/2) * y with y^2 ≡ a/p^v (mod p^(e-v))
+    let mut v = 0;
+    let mut unit = a;
+    while unit.is_multiple_of(p) {
+        unit /= p;
+        v += 1;
+    }
+    if v % 2 == 1 {
+        return RootSet::new(vec![], pe, 0);
+    }
+    let e1 = e - v;
+    let pe1 = p.pow(e1);
+    let ys = if p == 2 { sqrt_mod_unit_pow2(unit, e1) } else { sqrt_mod_unit_odd(unit, p, e1) };
+
+    // each root y modulo p^(e-v) gives p^(v/2) roots h * (y + j * p^(e-v)) modulo p^e
+    let h = p.pow(v / 2);
+    RootSet::new(ys.iter().map(|&y| h * y).collect(), h * pe1, h)
+}
+
+/// Square roots of a unit modulo p^e for an odd prime p, by Hensel lifting
+fn sqrt_mod_unit_odd(a: u64, p: u64, e: u32) -> Vec<u64> {
+    let mut r = match sqrt_mod_prime(a, p) {
+        Some(r) => r, None => return vec![]
+    };
+
+    // Newton's iteration r <- r - (r^2 - a) / 2r doubles the precision each step
+    let mut precision = 1;
+    while precision < e {
+        precision = (precision * 2).min(e);
+        let m = p.pow(precision);
+        let f = r.mul_mod(r, &m).sub_mod(a, &m);
+        let df = (2 * r % m).inv_mod(&m).unwrap();
+        r = r.sub_mod(f.mul_mod(df, &m), &m);
+    }
+
+    let pe = p.pow(e);
+    let mut roots = vec![r, pe - r];
+    roots.sort_unstable();
+    roots
+}
+
+/// Square roots of an odd number modulo 2^e
+fn sqrt_mod_unit_pow2(a: u64, e: u32) -> Vec<u64> {
+    match e {
+        1 => return vec![1],
+        2 => return if a % 4 == 1 { vec![1, 3] } else { vec![] },
+        _ => if a % 8 != 1 { return vec![] }
+    }
+
+    // lift the root r of x^2 ≡ a (mod 2^i) to modulo 2^(i+1)
+    let mut r: u64 = 1;
+    for i in 3..e {
+        let m = 1u128 << (i + 1);
+        if !((r as u128) * (r as u128) + m - (a as u128) % m).is_multiple_of(m) {
+            r += 1 << (i - 1);
+        }
+    }
+
+    let pe = 1u64 << e;
+    let half = pe >> 1;
+    let mut roots = vec![r, pe - r, (r + half) % pe, (pe - r + half) % pe];
+    roots.sort_unstable();
+    roots
+}
+
+/// Returns all k-th roots of a modulo a prime p in increasing order. `factors` is the
+/// factorization of gcd(k, p - 1).
+pub fn nth_root_mod_prime(a: u64, k: u64, p: u64, factors: &HashMap<u64, usize>) -> Vec<u64> {
+    assert!(k > 0);
+    let a = a % p;
+    if a == 0 || p == 2 {
+        return vec![a];
+    }
+
+    // a is a k-th residue iff a^((p-1)/d) = 1
+    let m = p - 1;
+    let d = k.gcd(&m);
+    if a.pow_mod(m / d, &p) != 1 {
+        return vec![];
+    }
+
+    // with b = a^u and u = (k/d)^-1 (mod m/d), we have b^(k/d) = a, so x^k = a if x^d = b
+    let md = m / d;
+    let u = if md == 1 { 0 } else { (k / d % md).inv_mod(&md).unwrap() };
+    let mut x = a.pow_mod(u, &p);
+
+    // take the r^e-th roots successively, and build a generator of the d-th roots of unity
+    let mut unity = 1;
+    for (&r, &e) in factors {
+        let sylow = SylowSubgroup::new(r, p);
+        x = sylow.root(x, e as u32);
+        unity = unity.mul_mod(sylow.generator.pow_mod(r.pow(sylow.exponent - e as u32), &p), &p);
+    }
+
+    let mut roots = Vec::with_capacity(d as usize);
+    for _ in 0..d {
+        roots.push(x);
+        x = x.mul_mod(unity, &p);
+    }
+    roots.sort_unstable();
+    roots
+}
+
+/// The Sylow r-subgroup of the multiplicative group modulo a prime p
+struct SylowSubgroup {
+    r: u64,
+    p: u64,
+    exponent: u32, // the subgroup has order r^exponent
+    cofactor: u64, // (p - 1) / r^exponent
+    generator: u64
+}
+
+impl SylowSubgroup {
+    fn new(r: u64, p: u64) -> Self {
+        let mut cofactor = p - 1;
+        let mut exponent = 0;
+        while cofactor.is_multiple_of(r) {
+            cofactor /= r;
+            exponent += 1;
+        }
+        assert!(exponent > 0);
+
+        // z^cofactor generates the subgroup if z is not a r-th residue
+        let mut z = 2;
+        while z.pow_mod((p - 1) / r, &p) == 1 {
+            z += 1;
+        }
+        SylowSubgroup { r, p, exponent, cofactor, generator: z.pow_mod(cofactor, &p) }
+    }
+
+    /// Returns the logarithm of h in the subgroup with respect to the generator,
+    /// using Pohlig-Hellman algorithm on the digits in base r
+    fn log(&self, h: u64) -> u64 {
+        let (r, p) = (self.r, self.p);
+        let gamma = self.generator.pow_mod(r.pow(self.exponent - 1), &p); // order r
+        let mut table = HashMap::new();
+        let mut power = 1;
+        for i in 0..r {
+            table.insert(power, i);
+            power = power.mul_mod(gamma, &p);
+        }
+
+        let ginv = self.generator.inv_mod(&p).unwrap();
+        let mut j = 0;
+        let mut rk = 1;
+        for k in 0..self.exponent {
+            let hk = ginv.pow_mod(j, &p).mul_mod(h, &p).pow_mod(r.pow(self.exponent - 1 - k), &p);
+            j += table[&hk] * rk;
+            rk *= r;
+        }
+        j
+    }
+
+    /// Returns a r^e-th root of b, which has to be a r^e-th residue
+    fn root(&self, b: u64, e: u32) -> u64 {
+        let p = self.p;
+        let re = self.r.pow(e);
+
+        // x0 = b^α with α = (r^e)^-1 (mod cofactor), then x0^(r^e) / b is in the subgroup
+        let alpha = if self.cofactor == 1 { 0 } else { (re % self.cofactor).inv_mod(&self.cofactor).unwrap() };
+        let x0 = b.pow_mod(alpha, &p);
+        let err = x0.pow_mod(re, &p).mul_mod(b.inv_mod(&p).unwrap(), &p);
+
+        // find y in the subgroup with y^(r^e) = err^-1
+        let j = self.log(err.inv_mod(&p).unwrap());
+        debug_assert!(j.is_multiple_of(re));
+        x0.mul_mod(self.generator.pow_mod(j / re, &p), &p)
+    }
+}
+
+/// Iterator over the solutions modulo n = m_1 * m_2 * ... combined by the Chinese
+/// remainder theorem from the solutions modulo each coprime m_i.
+pub struct CrtRoots {
+    n: u64,
+    residues: Vec<RootSet>,
+    coeffs: Vec<u64>, // x = sum(r_i * coeff_i) % n
+    index: Vec<u64>,
+    done: bool
+}
+
+impl CrtRoots {
+    /// Create the iterator from pairs of (solutions modulo m_i, m_i)
+    pub fn new(components: Vec<(RootSet, u64)>) -> Self {
+        let n: u64 = components.iter().map(|(_, m)| m).product();
+        let coeffs = components.iter().map(|&(_, m)| {
+            let rest = n / m;
+            rest.mul_mod((rest % m).inv_mod(&m).unwrap(), &n)
+        }).collect();
+        let done = components.iter().any(|(r, _)| r.is_empty());
+        let index = vec![0; components.len()];
+        let residues = components.into_iter().map(|(r, _)| r).collect();
+        CrtRoots { n, residues, coeffs, index, done }
+    }
+}
+
+impl Iterator for CrtRoots {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.done {
+            return None;
+        }
+
+        let n = self.n;
+        let x = self.index.iter().zip(&self.residues).zip(&self.coeffs)
+            .fold(0u64, |acc, ((&i, r), &c)| acc.add_mod(r.get(i).mul_mod(c, &n), &n));
+
+        // increase the mixed-radix index
+        self.done = true;
+        for (i, r) in self.index.iter_mut().zip(&self.residues) {
+            *i += 1;
+            if *i < r.len() {
+                self.done = false;
+                break;
+            }
+            *i = 0;
+        }
+        Some(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_prime_test() {
+        for &p in &[3u64, 5, 13, 17, 97, 257, 65537, 998244353, 1000000007, 18446744073709551557] {
+            for a in [0u64, 1, 2, 3, 4, 5, 10, 12345, p - 1] {
+                match sqrt_mod_prime(a, p) {
+                    Some(r) => assert_eq!(r.mul_mod(r, &p), a % p),
+                    None => assert_eq!(crate::int64::jacobi(a, p), -1)
+                }
+            }
+        }
+        // p - 1 = 119 * 2^23, which is solved by Cipolla by default
+        let (p, a) = (998244353u64, 123456789u64.mul_mod(123456789, &998244353));
+        let r = tonelli_shanks(a, p);
+        assert_eq!(r.mul_mod(r, &p), a);
+        let r = cipolla(a, p);
+        assert_eq!(r.mul_mod(r, &p), a);
+    }
+
+    #[test]
+    fn sqrt_prime_power_test() {
+        for &(p, e) in &[(2u64, 1u32), (2, 2), (2, 3), (2, 7), (3, 4), (5, 3), (7, 3), (13, 2)] {
+            let pe = p.pow(e);
+            for a in 0..pe {
+                let expected: Vec<u64> = (0..pe).filter(|&x| x * x % pe == a).collect();
+                assert_eq!(sqrt_mod_prime_power(a, p, e).iter().collect::<Vec<_>>(), expected);
+            }
+        }
+
+        // 2^31 roots of 0 modulo 2^62, and 3^10 roots of 3^20 * 4 modulo 3^40
+        let roots = sqrt_mod_prime_power(0, 2, 62);
+        assert_eq!(roots.len(), 1 << 31);
+        assert_eq!(roots.get(5), 5 << 31);
+        let (p, e) = (3u64, 40);
+        let pe = p.pow(e);
+        let roots = sqrt_mod_prime_power(p.pow(20) * 4, p, e);
+        assert_eq!(roots.len(), 2 * p.pow(10));
+        assert!(roots.iter().step_by(997).all(|x| x.mul_mod(x, &pe) == p.pow(20) * 4));
+        assert!(roots.iter().zip(roots.iter().skip(1)).all(|(x, y)| x < y));
+    }
+
+    #[test]
+    fn nth_root_test() {
+        let mut pb = crate::prime::PrimeBuffer::new();
+        for &p in &[2u64, 3, 7, 13, 31, 37, 41, 61, 73, 97] {
+            for k in 1..13u64 {
+                let factors = if k.gcd(&(p - 1)) == 1 { HashMap::new() } else { pb.factors(k.gcd(&(p - 1))) };
+                for a in 0..p {
+                    let expected: Vec<u64> = (0..p).filter(|&x| x.pow_mod(k, &p) == a).collect();
+                    assert_eq!(nth_root_mod_prime(a, k, p, &factors), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Integer partition functions
+//!
+//! Partitions into parts from a given set (e.g. the primes from `PrimeBuffer::primes`) are
+//! counted by the usual knapsack style dynamic programming.
+
+use num_bigint::BigUint;
+use crate::combinatorics::{Ring, Exact, Modular};
+
+// p(i) = sum((-1)^(k+1) * (p(i - k(3k-1)/2) + p(i - k(3k+1)/2))) by Euler's pentagonal number theorem
+fn partition_numbers_in<R: Ring>(r: &R, n: usize) -> Vec<R::Item> {
+    let mut result = vec![r.num(1)];
+    for i in 1..=n {
+        let (mut positive, mut negative) = (r.num(0), r.num(0));
+        for k in 1.. {
+            let g1 = k * (3 * k - 1) / 2;
+            if g1 > i {
+                break;
+            }
+            let mut term = result[i - g1].clone();
+            let g2 = g1 + k; // k(3k+1)/2
+            if g2 <= i {
+                term = r.add(&term, &result[i - g2]);
+            }
+            if k % 2 == 1 {
+                positive = r.add(&positive, &term);
+            } else {
+                negative = r.add(&negative, &term);
+            }
+        }
+        result.push(r.sub(&positive, &negative));
+    }
+    result
+}
+
+// each part can be used any number of times
+fn restricted_partitions_in<R: Ring>(r: &R, n: usize, parts: &[u64]) -> Vec<R::Item> {
+    let mut result = vec![r.num(0); n + 1];
+    result[0] = r.num(1);
+    for &part in parts {
+        let part = part as usize;
+        assert!(part > 0);
+        for i in part..=n {
+            result[i] = r.add(&result[i], &result[i - part]);
+        }
+    }
+    result
+}
+
+// each part can be used at most once
+fn distinct_partitions_in<R: Ring>(r: &R, n: usize, parts: &[u64]) -> Vec<R::Item> {
+    let mut result = vec![r.num(0); n + 1];
+    result[0] = r.num(1);
+    for &part in parts {
+        let part = part as usize;
+        assert!(part > 0);
+        for i in (part..=n).rev() {
+            result[i] = r.add(&result[i], &result[i - part]);
+        }
+    }
+    result
+}
+
+/// Returns the number of partitions p(n)
+pub fn partition(n: usize) -> BigUint { partition_numbers_in(&Exact, n).pop().unwrap() }
+
+/// Returns the number of partitions p(n) modulo m
+pub fn partition_mod(n: usize, m: u64) -> u64 { partition_numbers_in(&Modular(m), n).pop().unwrap() }
+
+/// Returns the numbers of partitions p(0), ..., p(n). It takes O(n^1.5) operations.
+pub fn partition_numbers(n: usize) -> Vec<BigUint> { partition_numbers_in(&Exact, n) }
+
+/// Returns the numbers of partitions p(0), ..., p(n) modulo m
+pub fn partition_numbers_mod(n: usize, m: u64) -> Vec<u64> { partition_numbers_in(&Modular(m), n) }
+
+/// Returns the numbers of partitions of 0, ..., n into the given parts, each part can be
+/// used repeatedly. The parts should be distinct.
+pub fn restricted_partitions(n: usize, parts: &[u64]) -> Vec<BigUint> { restricted_partitions_in(&Exact, n, parts) }
+
+/// Returns the numbers of partitions of 0, ..., n into the given parts modulo m
+pub fn restricted_partitions_mod(n: usize, parts: &[u64], m: u64) -> Vec<u64> { restricted_partitions_in(&Modular(m), n, parts) }
+
+/// Returns the numbers of partitions of 0, ..., n into distinct parts q(0), ..., q(n)
+pub fn distinct_partitions(n: usize) -> Vec<BigUint> {
+    distinct_partitions_in(&Exact, n, &(1..=n as u64).collect::<Vec<_>>())
+}
+
+/// Returns the numbers of partitions of 0, ..., n into distinct parts modulo m
+pub fn distinct_partitions_mod(n: usize, m: u64) -> Vec<u64> {
+    distinct_partitions_in(&Modular(m), n, &(1..=n as u64).collect::<Vec<_>>())
+}
+
+/// Returns the numbers of partitions of 0, ..., n into distinct parts from the given ones.
+/// The parts should be distinct.
+pub fn restricted_distinct_partitions(n: usize, parts: &[u64]) -> Vec<BigUint> { distinct_partitions_in(&Exact, n, parts) }
+
+/// Returns the numbers of partitions of 0, ..., n into distinct parts from the given ones modulo m
+pub fn restricted_distinct_partitions_mod(n: usize, parts: &[u64], m: u64) -> Vec<u64> { distinct_partitions_in(&Modular(m), n, parts) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+    use crate::prime::PrimeBuffer;
+
+    #[test]
+    fn partition_test() {
+        let p = partition_numbers(200);
+        let small: Vec<u64> = p[..12].iter().map(|x| x.to_u64().unwrap()).collect();
+        assert_eq!(small, [1, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42, 56]);
+        assert_eq!(p[100], BigUint::from(190569292u64));
+        assert_eq!(p[200], BigUint::from(3972999029388u64));
+        assert_eq!(partition(1000).to_string(), "24061467864032622473692149727991");
+
+        // the pentagonal recurrence agrees with the unrestricted knapsack
+        let parts: Vec<u64> = (1..=200).collect();
+        assert_eq!(restricted_partitions(200, &parts), p);
+        assert_eq!(partition_numbers_mod(200, 1000007), restricted_partitions_mod(200, &parts, 1000007));
+        assert_eq!(partition_mod(200, 1000000), 29388);
+
+        // Project Euler 78
+        let p = partition_numbers_mod(60000, 1000000);
+        assert_eq!(p.iter().position(|&x| x == 0), Some(55374));
+    }
+
+    #[test]
+    fn restricted_test() {
+        // Project Euler 76 and 77
+        let p = restricted_partitions(100, &(1..100).collect::<Vec<_>>());
+        assert_eq!(p[100], BigUint::from(190569291u64));
+        let mut pb = PrimeBuffer::new();
+        let primes = pb.primes(100).to_vec();
+        let p = restricted_partitions(100, &primes);
+        assert_eq!(p.iter().position(|x| x > &BigUint::from(5000u64)), Some(71));
+
+        let q = distinct_partitions(100);
+        let small: Vec<u64> = q[..11].iter().map(|x| x.to_u64().unwrap()).collect();
+        assert_eq!(small, [1, 1, 1, 2, 2, 3, 4, 5, 6, 8, 10]);
+        assert_eq!(q[100], BigUint::from(444793u64));
+        assert_eq!(distinct_partitions_mod(100, 1000)[100], 793);
+
+        // distinct parts are equinumerous with odd parts
+        let odd: Vec<u64> = (1..=100).step_by(2).collect();
+        assert_eq!(restricted_partitions(100, &odd), q);
+        let q = restricted_distinct_partitions_mod(100, &primes, 97);
+        assert_eq!(q[10], 2); // 10 = 7 + 3 = 5 + 3 + 2
+    }
+}
@@ -0,0 +1,415 @@
+//! Polynomial arithmetic by number-theoretic transforms
+//!
+//! Polynomials are coefficient vectors [a_0, a_1, ...] of residues modulo an NTT-friendly
+//! prime p, i.e. p - 1 is divisible by a large power of two (such as 998244353 = 119 * 2^23 + 1).
+//! The power series operations (inverse, log, exp, sqrt) return the first n coefficients.
+
+use crate::modroot::sqrt_mod_prime;
+use crate::traits::{ModInt, ModUnary};
+
+/// The NTT-friendly primes used for the convolution with an arbitrary modulus, their
+/// product is about 2^86.02
+pub const NTT_PRIMES: [u64; 3] = [998244353, 167772161, 469762049];
+
+/// The primitive roots of `NTT_PRIMES`
+const NTT_ROOTS: [u64; 3] = [3, 3, 3];
+
+/// Below this size the convolution is done directly
+const NAIVE_THRESHOLD: usize = 32;
+
+/// Returns a primitive root modulo an NTT-friendly prime p, from the table for `NTT_PRIMES`
+fn ntt_root(p: u64) -> u64 {
+    match NTT_PRIMES.iter().position(|&q| q == p) {
+        Some(i) => NTT_ROOTS[i],
+        None => find_ntt_root(p)
+    }
+}
+
+/// Returns the smallest primitive root modulo an NTT-friendly prime p
+fn find_ntt_root(p: u64) -> u64 {
+    // the odd part of p - 1 is small, so trial division is enough to factor it
+    let mut factors = vec![2];
+    let mut c = (p - 1) >> (p - 1).trailing_zeros();
+    let mut q = 3;
+    while q * q <= c {
+        if c.is_multiple_of(q) {
+            factors.push(q);
+            while c.is_multiple_of(q) {
+                c /= q;
+            }
+        }
+        q += 2;
+    }
+    if c > 1 {
+        factors.push(c);
+    }
+    (2..).find(|g: &u64| factors.iter().all(|q| g.pow_mod((p - 1) / q, &p) != 1)).unwrap()
+}
+
+/// In-place number-theoretic transform modulo p, the length of the input has to be a power
+/// of two dividing p - 1. The inverse transform includes the division by the length.
+pub fn ntt(a: &mut [u64], invert: bool, p: u64) {
+    let n = a.len();
+    assert!(n.is_power_of_two() && (p - 1).is_multiple_of(n as u64));
+
+    // bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit > 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j ^= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+
+    let g = ntt_root(p);
+    let g = if invert { g.inv_mod(&p).unwrap() } else { g };
+    let mut len = 2;
+    while len <= n {
+        let w = g.pow_mod((p - 1) / len as u64, &p);
+        for chunk in a.chunks_mut(len) {
+            let (lo, hi) = chunk.split_at_mut(len / 2);
+            let mut wi = 1;
+            for (x, y) in lo.iter_mut().zip(hi.iter_mut()) {
+                let (u, v) = (*x, y.mul_mod(wi, &p));
+                *x = u.add_mod(v, &p);
+                *y = u.sub_mod(v, &p);
+                wi = wi.mul_mod(w, &p);
+            }
+        }
+        len <<= 1;
+    }
+
+    if invert {
+        let inv_n = (n as u64).inv_mod(&p).unwrap();
+        for x in a.iter_mut() {
+            *x = x.mul_mod(inv_n, &p);
+        }
+    }
+}
+
+/// Returns the product of two polynomials modulo an NTT-friendly prime p
+pub fn convolution(a: &[u64], b: &[u64], p: u64) -> Vec<u64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let len = a.len() + b.len() - 1;
+    if a.len().min(b.len()) <= NAIVE_THRESHOLD {
+        let mut result = vec![0; len];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                result[i + j] = result[i + j].add_mod(x.mul_mod(y, &p), &p);
+            }
+        }
+        return result;
+    }
+
+    let n = len.next_power_of_two();
+    let mut fa: Vec<u64> = a.iter().map(|x| x % p).collect();
+    let mut fb: Vec<u64> = b.iter().map(|x| x % p).collect();
+    fa.resize(n, 0);
+    fb.resize(n, 0);
+    ntt(&mut fa, false, p);
+    ntt(&mut fb, false, p);
+    for (x, y) in fa.iter_mut().zip(&fb) {
+        *x = x.mul_mod(y, &p);
+    }
+    ntt(&mut fa, true, p);
+    fa.truncate(len);
+    fa
+}
+
+/// Returns the product of two polynomials modulo an arbitrary m. The convolutions modulo
+/// `NTT_PRIMES` are combined by CRT, which is exact as long as min(len(a), len(b)) * (m-1)^2
+/// is below their product (about 2^86.02, e.g. m < 2^31 with lengths up to 2^23). The
+/// length of the product is limited to 2^23 by 998244353.
+pub fn convolution_mod(a: &[u64], b: &[u64], m: u64) -> Vec<u64> {
+    debug_assert!({
+        let product = NTT_PRIMES.iter().map(|&p| p as u128).product::<u128>();
+        let len = a.len().min(b.len()) as u128;
+        let mm = (m as u128 - 1).checked_mul(m as u128 - 1);
+        mm.and_then(|mm| mm.checked_mul(len)).is_some_and(|x| x < product)
+    }, "the coefficients of the product exceed the CRT range");
+    let a: Vec<u64> = a.iter().map(|x| x % m).collect();
+    let b: Vec<u64> = b.iter().map(|x| x % m).collect();
+    let [p1, p2, p3] = NTT_PRIMES;
+    let c1 = convolution(&a, &b, p1);
+    let c2 = convolution(&a, &b, p2);
+    let c3 = convolution(&a, &b, p3);
+
+    let inv_p1 = p1.inv_mod(&p2).unwrap();
+    let p12 = p1 * p2;
+    let inv_p12 = (p12 % p3).inv_mod(&p3).unwrap();
+    c1.iter().zip(&c2).zip(&c3).map(|((&x1, &x2), &x3)| {
+        // x ≡ x12 (mod p1 * p2), then x = x12 + p1 * p2 * t
+        let x12 = x1 + p1 * x2.sub_mod(x1, &p2).mul_mod(inv_p1, &p2);
+        let t = x3.sub_mod(x12 % p3, &p3).mul_mod(inv_p12, &p3);
+        (x12 % m).add_mod((p12 % m).mul_mod(t, &m), &m)
+    }).collect()
+}
+
+/// Returns the first n coefficients of 1 / a(x), a_0 has to be non-zero
+pub fn inverse(a: &[u64], n: usize, p: u64) -> Vec<u64> {
+    assert!(!a.is_empty() && !a[0].is_multiple_of(p));
+
+    // Newton's iteration g <- g * (2 - a * g)
+    let mut g = vec![a[0].inv_mod(&p).unwrap()];
+    let mut k = 1;
+    while k < n {
+        k *= 2;
+        let mut h = convolution(&a[..k.min(a.len())], &g, p);
+        h.resize(k, 0);
+        for x in h.iter_mut() {
+            *x = x.neg_mod(&p);
+        }
+        h[0] = h[0].add_mod(2, &p);
+        g = convolution(&g, &h, p);
+        g.truncate(k);
+    }
+    g.resize(n, 0);
+    g
+}
+
+fn derivative(a: &[u64], p: u64) -> Vec<u64> {
+    a.iter().enumerate().skip(1).map(|(i, x)| x.mul_mod(i as u64, &p)).collect()
+}
+
+/// Integrate with zero constant term, keeping the first n coefficients
+fn integral(a: &[u64], n: usize, p: u64) -> Vec<u64> {
+    let mut result = vec![0; n];
+    for i in 1..n.min(a.len() + 1) {
+        result[i] = a[i - 1].mul_mod((i as u64).inv_mod(&p).unwrap(), &p);
+    }
+    result
+}
+
+/// Returns the first n coefficients of log(a(x)), a_0 has to be 1
+pub fn log(a: &[u64], n: usize, p: u64) -> Vec<u64> {
+    assert!(!a.is_empty() && a[0] % p == 1);
+    let a = &a[..n.min(a.len())];
+    let mut q = convolution(&derivative(a, p), &inverse(a, n, p), p);
+    q.truncate(n);
+    integral(&q, n, p)
+}
+
+/// Returns the first n coefficients of exp(a(x)), a_0 has to be 0
+pub fn exp(a: &[u64], n: usize, p: u64) -> Vec<u64> {
+    assert!(a.is_empty() || a[0].is_multiple_of(p));
+
+    // Newton's iteration g <- g * (1 - log(g) + a)
+    let mut g = vec![1 % p];
+    let mut k = 1;
+    while k < n {
+        k *= 2;
+        let mut h = log(&g, k, p);
+        for (i, x) in h.iter_mut().enumerate() {
+            *x = a.get(i).unwrap_or(&0).sub_mod(*x, &p);
+        }
+        h[0] = h[0].add_mod(1, &p);
+        g = convolution(&g, &h, p);
+        g.truncate(k);
+    }
+    g.resize(n, 0);
+    g
+}
+
+/// Returns the first n coefficients of a square root of a(x), or None if it doesn't exist.
+/// The other root is the negation of the returned one.
+pub fn sqrt(a: &[u64], n: usize, p: u64) -> Option<Vec<u64>> {
+    // x^(2k) can be taken out if the lowest non-zero term is x^(2k)
+    let shift = match a.iter().position(|x| x % p != 0) {
+        Some(s) => s, None => return Some(vec![0; n])
+    };
+    if shift % 2 == 1 {
+        return None;
+    }
+    if shift / 2 >= n {
+        return Some(vec![0; n]);
+    }
+    let a = &a[shift..];
+    let m = n - shift / 2;
+
+    // Newton's iteration g <- (g + a / g) / 2
+    let mut g = vec![sqrt_mod_prime(a[0], p)?];
+    let inv2 = 2u64.inv_mod(&p).unwrap();
+    let mut k = 1;
+    while k < m {
+        k *= 2;
+        let mut h = convolution(&a[..k.min(a.len())], &inverse(&g, k, p), p);
+        h.resize(k, 0);
+        g.resize(k, 0);
+        for (x, y) in g.iter_mut().zip(&h) {
+            *x = x.add_mod(*y, &p).mul_mod(inv2, &p);
+        }
+    }
+    g.truncate(m);
+
+    let mut result = vec![0; shift / 2];
+    result.extend(g);
+    result.resize(n, 0);
+    Some(result)
+}
+
+/// Returns the quotient and the remainder of a(x) / b(x), the leading coefficient of b
+/// has to be non-zero
+pub fn divmod(a: &[u64], b: &[u64], p: u64) -> (Vec<u64>, Vec<u64>) {
+    assert!(!b.is_empty() && !b[b.len() - 1].is_multiple_of(p));
+    if a.len() < b.len() {
+        return (Vec::new(), a.to_vec());
+    }
+
+    // reversed polynomials turn the division into a power series inverse
+    let m = a.len() - b.len() + 1;
+    let ra: Vec<u64> = a.iter().rev().take(m).cloned().collect();
+    let rb: Vec<u64> = b.iter().rev().cloned().collect();
+    let mut q = convolution(&ra, &inverse(&rb, m, p), p);
+    q.truncate(m);
+    q.reverse();
+
+    let bq = convolution(b, &q, p);
+    let r = (0..b.len() - 1).map(|i| a[i].sub_mod(bq[i], &p)).collect();
+    (q, r)
+}
+
+/// Returns a(x) at each of the points by the remainders down a subproduct tree
+pub fn evaluate(a: &[u64], points: &[u64], p: u64) -> Vec<u64> {
+    const HORNER_THRESHOLD: usize = 8;
+
+    // tree[i] = product of (x - x_j) for the points under node i
+    fn build(tree: &mut Vec<Vec<u64>>, node: usize, points: &[u64], p: u64) {
+        if points.len() == 1 {
+            tree[node] = vec![points[0].neg_mod(&p), 1 % p];
+            return;
+        }
+        let mid = points.len() / 2;
+        build(tree, 2 * node, &points[..mid], p);
+        build(tree, 2 * node + 1, &points[mid..], p);
+        tree[node] = convolution(&tree[2 * node], &tree[2 * node + 1], p);
+    }
+
+    fn descend(tree: &[Vec<u64>], node: usize, r: &[u64], points: &[u64], p: u64, result: &mut Vec<u64>) {
+        if points.len() <= HORNER_THRESHOLD {
+            result.extend(points.iter().map(|x| r.iter().rev().fold(0, |acc, c| acc.mul_mod(*x, &p).add_mod(*c, &p))));
+            return;
+        }
+        let mid = points.len() / 2;
+        let left = divmod(r, &tree[2 * node], p).1;
+        descend(tree, 2 * node, &left, &points[..mid], p, result);
+        let right = divmod(r, &tree[2 * node + 1], p).1;
+        descend(tree, 2 * node + 1, &right, &points[mid..], p, result);
+    }
+
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let mut tree = vec![Vec::new(); 4 * points.len()];
+    build(&mut tree, 1, points, p);
+    let r = divmod(a, &tree[1], p).1;
+    let mut result = Vec::with_capacity(points.len());
+    descend(&tree, 1, &r, points, p, &mut result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    const P: u64 = 998244353;
+
+    fn naive(a: &[u64], b: &[u64], m: u64) -> Vec<u64> {
+        let mut result = vec![0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                result[i + j] = result[i + j].add_mod(x.mul_mod(y, &m), &m);
+            }
+        }
+        result
+    }
+
+    fn random_poly(rng: &mut StdRng, n: usize, m: u64) -> Vec<u64> {
+        (0..n).map(|_| rng.gen_range(0..m)).collect()
+    }
+
+    #[test]
+    fn convolution_test() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for (&p, &g) in NTT_PRIMES.iter().zip(&NTT_ROOTS) {
+            assert_eq!(find_ntt_root(p), g);
+        }
+        assert_eq!(ntt_root(754974721), 11);
+        for &(n, m) in &[(1usize, 1usize), (33, 40), (100, 300), (257, 1000)] {
+            let a = random_poly(&mut rng, n, P);
+            let b = random_poly(&mut rng, m, P);
+            assert_eq!(convolution(&a, &b, P), naive(&a, &b, P));
+
+            let q = 1000000007;
+            let a = random_poly(&mut rng, n, q);
+            let b = random_poly(&mut rng, m, q);
+            assert_eq!(convolution_mod(&a, &b, q), naive(&a, &b, q));
+        }
+
+        let mut a = vec![1, 2, 3, 4];
+        ntt(&mut a, false, P);
+        ntt(&mut a, true, P);
+        assert_eq!(a, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn series_test() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let n = 200;
+        let mut a = random_poly(&mut rng, n, P);
+        a[0] = 1;
+
+        let inv = inverse(&a, n, P);
+        let mut prod = convolution(&a, &inv, P);
+        prod.truncate(n);
+        assert_eq!(prod[0], 1);
+        assert!(prod[1..].iter().all(|&x| x == 0));
+
+        assert_eq!(exp(&log(&a, n, P), n, P), a);
+
+        let mut sq = convolution(&a, &a, P);
+        sq.truncate(n);
+        let root = sqrt(&sq, n, P).unwrap();
+        assert!(root == a || root.iter().zip(&a).all(|(x, y)| x.add_mod(*y, &P) == 0));
+        let shifted: Vec<u64> = [0, 0].iter().chain(&sq).cloned().collect();
+        assert_eq!(sqrt(&shifted, n, P).unwrap()[0], 0);
+        assert!(sqrt(&[0, 1], n, P).is_none());
+        assert!(sqrt(&[5], 1, P).is_none()); // 5 is a non-residue
+
+        // exp(x) = sum(x^k / k!)
+        let e = exp(&[0, 1], 10, P);
+        let mut factorial = 1;
+        for (k, &c) in e.iter().enumerate() {
+            if k > 0 {
+                factorial = factorial.mul_mod(k as u64, &P);
+            }
+            assert_eq!(c.mul_mod(factorial, &P), 1);
+        }
+    }
+
+    #[test]
+    fn evaluate_test() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let a = random_poly(&mut rng, 150, P);
+        let b = random_poly(&mut rng, 60, P);
+        let (q, r) = divmod(&a, &b, P);
+        let mut back = convolution(&b, &q, P);
+        for (x, y) in back.iter_mut().zip(&r) {
+            *x = x.add_mod(*y, &P);
+        }
+        assert_eq!(back, a);
+
+        let points = random_poly(&mut rng, 100, P);
+        let values = evaluate(&a, &points, P);
+        for (x, v) in points.iter().zip(&values) {
+            assert_eq!(a.iter().rev().fold(0, |acc, c| acc.mul_mod(*x, &P).add_mod(*c, &P)), *v);
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Exact univariate polynomials over integers, rationals and modular integers
+//!
+//! The arithmetic works for any ring-like coefficient type (e.g. `i64`, `BigInt`,
+//! `BigRational`, `Mod<P>`). Division, gcd and interpolation need a `Field`, while integer
+//! polynomials have their own primitive gcd and rational root search. For the NTT based
+//! arithmetic modulo a prime, see `poly`.
+
+use std::ops::{Add, Sub, Mul, Neg};
+use num_traits::{Zero, One, Signed};
+use num_integer::Integer;
+use num_rational::Ratio;
+use crate::matrix::Field;
+
+/// A polynomial a_0 + a_1 x + ... + a_n x^n, stored without trailing zero coefficients
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Polynomial<T> {
+    coeffs: Vec<T>
+}
+
+impl<T: Clone + Zero> Polynomial<T> {
+    /// Create a polynomial from the coefficients [a_0, a_1, ...]
+    pub fn new(mut coeffs: Vec<T>) -> Self {
+        while let Some(c) = coeffs.last() {
+            if !c.is_zero() {
+                break;
+            }
+            coeffs.pop();
+        }
+        Polynomial { coeffs }
+    }
+
+    /// Returns true for the zero polynomial
+    #[inline]
+    pub fn is_zero(&self) -> bool { self.coeffs.is_empty() }
+
+    /// Returns the coefficients [a_0, ..., a_n], which is empty for the zero polynomial
+    #[inline]
+    pub fn coeffs(&self) -> &[T] { &self.coeffs }
+
+    /// Returns the degree, or None for the zero polynomial
+    #[inline]
+    pub fn degree(&self) -> Option<usize> { self.coeffs.len().checked_sub(1) }
+
+    /// Returns the leading coefficient, or None for the zero polynomial
+    #[inline]
+    pub fn leading(&self) -> Option<&T> { self.coeffs.last() }
+
+    /// Returns the coefficient of x^k
+    pub fn coeff(&self, k: usize) -> T {
+        self.coeffs.get(k).cloned().unwrap_or_else(T::zero)
+    }
+
+    /// Apply a function to each coefficient
+    pub fn map<U: Clone + Zero, F: FnMut(&T) -> U>(&self, f: F) -> Polynomial<U> {
+        Polynomial::new(self.coeffs.iter().map(f).collect())
+    }
+}
+
+impl<T: Clone + Zero + One + Sub<Output = T>> Polynomial<T> {
+    /// Returns the constant polynomial c
+    pub fn constant(c: T) -> Self { Self::new(vec![c]) }
+
+    /// Returns c * x^k
+    pub fn monomial(c: T, k: usize) -> Self {
+        let mut coeffs = vec![T::zero(); k];
+        coeffs.push(c);
+        Self::new(coeffs)
+    }
+
+    /// Returns the polynomial x
+    pub fn x() -> Self { Self::monomial(T::one(), 1) }
+
+    /// Evaluate at x by Horner's method
+    pub fn eval(&self, x: &T) -> T {
+        self.coeffs.iter().rev().fold(T::zero(), |acc, c| acc * x.clone() + c.clone())
+    }
+
+    /// Multiply each coefficient by c
+    pub fn scale(&self, c: &T) -> Self {
+        self.map(|a| a.clone() * c.clone())
+    }
+
+    /// Returns the formal derivative
+    pub fn derivative(&self) -> Self {
+        let mut k = T::zero();
+        let coeffs = self.coeffs.iter().skip(1).map(|c| {
+            k = k.clone() + T::one();
+            c.clone() * k.clone()
+        }).collect();
+        Self::new(coeffs)
+    }
+}
+
+impl<T: Clone + Zero> Add for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn add(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+        let n = self.coeffs.len().max(rhs.coeffs.len());
+        Polynomial::new((0..n).map(|i| self.coeff(i) + rhs.coeff(i)).collect())
+    }
+}
+
+impl<T: Clone + Zero + Sub<Output = T>> Sub for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn sub(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+        let n = self.coeffs.len().max(rhs.coeffs.len());
+        Polynomial::new((0..n).map(|i| self.coeff(i) - rhs.coeff(i)).collect())
+    }
+}
+
+impl<T: Clone + Zero + Sub<Output = T>> Neg for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Polynomial<T> {
+        self.map(|c| T::zero() - c.clone())
+    }
+}
+
+impl<T: Clone + Zero + Mul<Output = T>> Mul for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: &Polynomial<T>) -> Polynomial<T> {
+        if self.coeffs.is_empty() || rhs.coeffs.is_empty() {
+            return Polynomial { coeffs: Vec::new() };
+        }
+        let mut coeffs = vec![T::zero(); self.coeffs.len() + rhs.coeffs.len() - 1];
+        for (i, a) in self.coeffs.iter().enumerate() {
+            if a.is_zero() {
+                continue;
+            }
+            for (j, b) in rhs.coeffs.iter().enumerate() {
+                coeffs[i + j] = coeffs[i + j].clone() + a.clone() * b.clone();
+            }
+        }
+        Polynomial::new(coeffs)
+    }
+}
+
+impl<T: Field> Polynomial<T> {
+    /// Returns the quotient and the remainder of the division by a non-zero polynomial
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let d = rhs.degree().expect("division by zero polynomial");
+        let lead = rhs.coeffs[d].clone();
+        let mut r = self.coeffs.clone();
+        if r.len() <= d {
+            return (Polynomial::new(Vec::new()), self.clone());
+        }
+        let mut q = vec![T::zero(); r.len() - d];
+        for k in (0..q.len()).rev() {
+            let c = r[k + d].clone() / lead.clone();
+            for (j, b) in rhs.coeffs.iter().enumerate() {
+                r[k + j] = r[k + j].clone() - c.clone() * b.clone();
+            }
+            q[k] = c;
+        }
+        r.truncate(d);
+        (Polynomial::new(q), Polynomial::new(r))
+    }
+
+    /// Divide by the leading coefficient
+    pub fn monic(&self) -> Self {
+        match self.leading() {
+            Some(lead) => self.map(|c| c.clone() / lead.clone()),
+            None => self.clone()
+        }
+    }
+
+    /// Returns the monic greatest common divisor by Euclid's algorithm
+    pub fn gcd(&self, rhs: &Self) -> Self {
+        let (mut a, mut b) = (self.clone(), rhs.clone());
+        while !b.coeffs.is_empty() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    /// Returns the polynomial of the least degree passing through the points by Lagrange's
+    /// formula, the x values have to be distinct
+    pub fn lagrange(points: &[(T, T)]) -> Self {
+        // prod(x - x_j), then each basis polynomial is obtained by dividing out a factor
+        let full = points.iter().fold(Self::constant(T::one()), |acc, (x, _)| {
+            &acc * &Polynomial::new(vec![T::zero() - x.clone(), T::one()])
+        });
+        points.iter().fold(Polynomial::new(Vec::new()), |acc, (x, y)| {
+            let basis = full.div_rem(&Polynomial::new(vec![T::zero() - x.clone(), T::one()])).0;
+            let denom = basis.eval(x);
+            &acc + &basis.scale(&(y.clone() / denom))
+        })
+    }
+
+    /// Returns the same polynomial as `lagrange` by Newton's divided differences
+    pub fn newton(points: &[(T, T)]) -> Self {
+        let n = points.len();
+        let mut dd: Vec<T> = points.iter().map(|(_, y)| y.clone()).collect();
+        for j in 1..n {
+            for i in (j..n).rev() {
+                dd[i] = (dd[i].clone() - dd[i - 1].clone()) / (points[i].0.clone() - points[i - j].0.clone());
+            }
+        }
+
+        // c_0 + (x - x_0)(c_1 + (x - x_1)(c_2 + ...))
+        (0..n).rev().fold(Polynomial::new(Vec::new()), |acc, i| {
+            let factor = Polynomial::new(vec![T::zero() - points[i].0.clone(), T::one()]);
+            &(&acc * &factor) + &Self::constant(dd[i].clone())
+        })
+    }
+}
+
+impl<T: Clone + Integer> Polynomial<Ratio<T>> {
+    /// Returns the polynomial p with p(i) = seq[i] for all i, if its degree is less than
+    /// seq.len() - 1. At least one more term than the degree is needed to confirm.
+    pub fn fit_sequence(seq: &[T]) -> Option<Self> {
+        // leading terms of the finite differences, which become all zero after the degree
+        let mut heads = Vec::new();
+        let mut row = seq.to_vec();
+        while !row.iter().all(|x| x.is_zero()) {
+            if row.len() <= 1 {
+                return None;
+            }
+            heads.push(row[0].clone());
+            row = row.windows(2).map(|w| w[1].clone() - w[0].clone()).collect();
+        }
+        if seq.is_empty() {
+            return None;
+        }
+
+        // p(x) = sum(Δ^k a_0 * C(x, k))
+        let mut result = Polynomial::new(Vec::new());
+        let mut binomial = Self::constant(Ratio::one());
+        let mut k = T::zero();
+        for h in heads {
+            result = &result + &binomial.scale(&Ratio::from_integer(h));
+            k = k + T::one();
+            let factor = Polynomial::new(vec![Ratio::from_integer(T::one() - k.clone()), Ratio::one()]);
+            binomial = (&binomial * &factor).scale(&Ratio::new(T::one(), k.clone()));
+        }
+        Some(result)
+    }
+}
+
+/// Positive divisors of n by trial division
+fn divisors<T: Clone + Integer>(n: &T) -> Vec<T> {
+    let mut result = Vec::new();
+    let mut d = T::one();
+    while d.clone() * d.clone() <= *n {
+        if n.is_multiple_of(&d) {
+            result.push(d.clone());
+            result.push(n.clone() / d.clone());
+        }
+        d = d + T::one();
+    }
+    result
+}
+
+impl<T: Clone + Integer + Signed> Polynomial<T> {
+    /// Returns the gcd of the coefficients, with the sign of the leading coefficient
+    pub fn content(&self) -> T {
+        let g = self.coeffs.iter().fold(T::zero(), |acc, c| acc.gcd(c));
+        match self.leading() {
+            Some(lead) if lead.is_negative() => -g,
+            _ => g
+        }
+    }
+
+    /// Returns the polynomial divided by its content, which has a positive leading coefficient
+    pub fn primitive_part(&self) -> Self {
+        let c = self.content();
+        if c.is_zero() { self.clone() } else { self.map(|a| a.clone() / c.clone()) }
+    }
+
+    /// Returns lead(rhs)^k * self mod rhs for some k, without leaving the integers
+    fn pseudo_rem(&self, rhs: &Self) -> Self {
+        let d = rhs.degree().expect("division by zero polynomial");
+        let lead = rhs.coeffs[d].clone();
+        let mut r = self.clone();
+        while let Some(n) = r.degree().filter(|&n| n >= d) {
+            let c = r.coeffs[n].clone();
+            r = &r.scale(&lead) - &(rhs * &Self::monomial(c, n - d));
+        }
+        r
+    }
+
+    /// Returns the greatest common divisor over the integers, with a positive leading coefficient
+    pub fn primitive_gcd(&self, rhs: &Self) -> Self {
+        let (mut a, mut b) = (self.primitive_part(), rhs.primitive_part());
+        if a.degree() < b.degree() {
+            std::mem::swap(&mut a, &mut b);
+        }
+        while !b.coeffs.is_empty() {
+            let r = a.pseudo_rem(&b).primitive_part();
+            a = b;
+            b = r;
+        }
+        a.scale(&self.content().gcd(&rhs.content()))
+    }
+
+    /// Returns the distinct rational roots in increasing order. Candidates p/q with p | a_0
+    /// and q | a_n are tested, with the divisors found by trial division.
+    pub fn rational_roots(&self) -> Vec<Ratio<T>> {
+        let zeros = match self.coeffs.iter().position(|c| !c.is_zero()) {
+            Some(z) => z, None => return Vec::new()
+        };
+        let reduced = Polynomial::new(self.coeffs[zeros..].to_vec()).map(|c| Ratio::from_integer(c.clone()));
+        let mut roots = Vec::new();
+        if zeros > 0 {
+            roots.push(Ratio::zero());
+        }
+
+        let (first, last) = (self.coeffs[zeros].abs(), self.coeffs[self.coeffs.len() - 1].abs());
+        for p in divisors(&first) {
+            for q in divisors(&last) {
+                for r in [Ratio::new(p.clone(), q.clone()), Ratio::new(-p.clone(), q.clone())] {
+                    if reduced.eval(&r).is_zero() {
+                        roots.push(r);
+                    }
+                }
+            }
+        }
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use crate::modint::Mod;
+
+    fn linear(a: i64, b: i64) -> Polynomial<i64> { Polynomial::new(vec![b, a]) } // ax + b
+
+    #[test]
+    fn arithmetic_test() {
+        let p = &linear(1, 1) * &linear(1, -2);
+        assert_eq!(p.coeffs(), [-2, -1, 1]);
+        assert_eq!(p.eval(&5), 18);
+        assert_eq!(p.derivative().coeffs(), [-1, 2]);
+        assert_eq!((&p - &p).degree(), None);
+        assert_eq!((&p + &Polynomial::x()).coeffs(), [-2, 0, 1]);
+        assert_eq!((-&p).leading(), Some(&-1));
+        assert!(Polynomial::<i64>::new(vec![0, 0]).is_zero());
+
+        let a = &(&linear(1, -1) * &linear(2, 3)).scale(&6) * &linear(1, 4);
+        let b = &(&linear(1, -1) * &linear(-2, -3)).scale(&4) * &linear(1, 5);
+        assert_eq!(a.content(), 6);
+        assert_eq!(b.content(), -4);
+        assert_eq!(a.primitive_gcd(&b), (&linear(1, -1) * &linear(2, 3)).scale(&2));
+
+        // x(2x - 1)(3x + 2)
+        let c = &(&Polynomial::x() * &linear(2, -1)) * &linear(3, 2);
+        assert_eq!(c.rational_roots(), [Ratio::new(-2, 3), Ratio::zero(), Ratio::new(1, 2)]);
+        assert!(Polynomial::new(vec![1, 0, 1]).rational_roots().is_empty());
+    }
+
+    #[test]
+    fn field_test() {
+        let r = |a: i64, b: i64| Ratio::new(a, b);
+        let p = Polynomial::new(vec![r(-2, 1), r(-1, 1), r(1, 1)]); // (x + 1)(x - 2)
+        let q = Polynomial::new(vec![r(3, 1), r(2, 1)]); // 2x + 3
+        let (quo, rem) = p.div_rem(&q);
+        assert_eq!(&(&quo * &q) + &rem, p);
+        assert_eq!(rem.degree(), Some(0));
+
+        let s = &p * &Polynomial::new(vec![r(1, 1), r(1, 1)]);
+        assert_eq!(p.gcd(&s), p);
+        assert_eq!(p.gcd(&q), Polynomial::constant(r(1, 1)));
+
+        type M = Mod<1000000007>;
+        let points: Vec<(M, M)> = (1..6u64).map(|x| (M::from(x), M::from(x * x * x + 7))).collect();
+        let f = Polynomial::lagrange(&points);
+        assert_eq!(f, Polynomial::newton(&points));
+        assert_eq!(f.coeffs(), [M::from(7u64), M::zero(), M::zero(), M::one()]);
+    }
+
+    #[test]
+    fn fit_test() {
+        // sum of squares is n(n + 1)(2n + 1) / 6
+        let seq: Vec<BigInt> = (0..10).map(|n| BigInt::from((1..=n).map(|i| i * i).sum::<i64>())).collect();
+        let f = Polynomial::fit_sequence(&seq).unwrap();
+        assert_eq!(f.degree(), Some(3));
+        let n = BigRational::from_integer(BigInt::from(1000000));
+        assert_eq!(f.eval(&n).to_integer(), BigInt::from(333333833333500000i64));
+
+        let points: Vec<(BigRational, BigRational)> = seq.iter().enumerate().take(4)
+            .map(|(i, y)| (BigRational::from_integer(BigInt::from(i)), BigRational::from_integer(y.clone()))).collect();
+        assert_eq!(Polynomial::lagrange(&points), f);
+
+        assert_eq!(Polynomial::fit_sequence(&[1i64, 2, 4, 8, 16, 32]), None);
+        assert_eq!(Polynomial::fit_sequence(&[5i64, 5]), Some(Polynomial::constant(Ratio::from_integer(5))));
+        assert_eq!(Polynomial::fit_sequence(&[5i64]), None);
+        assert_eq!(Polynomial::fit_sequence(&[0i64]), Some(Polynomial::new(Vec::new())));
+    }
+}
@@ -0,0 +1,122 @@
+//! Sums of powers 1^k + 2^k + ... + n^k and Bernoulli numbers
+//!
+//! The exact and modular sums are computed through the Stirling numbers of the second kind,
+//!   sum(i^k for i in 0..=n) = sum(S(k, j) * j! * C(n+1, j+1) for j in 0..=k)
+//! where j! * C(n+1, j+1) is a product of j+1 consecutive integers divided by j+1, so no
+//! modular inverse is needed and any modulus works. It takes O(k^2) operations.
+
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+use crate::combinatorics::{Ring, Exact, Modular, last_row, stirling2_next, binomial_row};
+use crate::polynomial::Polynomial;
+
+fn power_sum_in<R: Ring>(r: &R, n: u64, k: usize) -> R::Item {
+    if k == 0 {
+        return r.num(n);
+    }
+    let stirling = last_row(r, k, stirling2_next);
+    let mut sum = r.num(0);
+    for (j, s) in stirling.iter().enumerate().skip(1) {
+        if j as u64 > n {
+            break; // C(n+1, j+1) = 0
+        }
+
+        // (n+1) * n * ... * (n+1-j) / (j+1), one of the factors is divisible by j+1
+        let divisor = j as u64 + 1;
+        let mut divided = false;
+        let product = (n - j as u64..=n).fold(r.num(1), |acc, mut t| {
+            t += 1;
+            if !divided && t % divisor == 0 {
+                t /= divisor;
+                divided = true;
+            }
+            r.mul(&acc, &r.num(t))
+        });
+        sum = r.add(&sum, &r.mul(s, &product));
+    }
+    sum
+}
+
+/// Returns 1^k + 2^k + ... + n^k
+pub fn power_sum(n: u64, k: usize) -> BigUint { power_sum_in(&Exact, n, k) }
+
+/// Returns 1^k + 2^k + ... + n^k modulo m, which doesn't need to be a prime
+pub fn power_sum_mod(n: u64, k: usize, m: u64) -> u64 { power_sum_in(&Modular(m), n, k) }
+
+/// Returns the Bernoulli numbers B_0, ..., B_n by Akiyama-Tanigawa algorithm, with the
+/// convention B_1 = -1/2
+pub fn bernoulli_numbers(n: usize) -> Vec<BigRational> {
+    let mut a = Vec::with_capacity(n + 1);
+    let mut result = Vec::with_capacity(n + 1);
+    for m in 0..=n {
+        a.push(BigRational::new(BigInt::one(), BigInt::from(m + 1)));
+        for j in (1..=m).rev() {
+            a[j - 1] = (&a[j - 1] - &a[j]) * BigInt::from(j);
+        }
+        result.push(a[0].clone()); // B_1 = +1/2 in this algorithm
+    }
+    if n >= 1 {
+        result[1] = -result[1].clone();
+    }
+    result
+}
+
+/// Returns the Bernoulli number B_n
+pub fn bernoulli(n: usize) -> BigRational { bernoulli_numbers(n).pop().unwrap() }
+
+/// Returns the Faulhaber polynomial p with p(n) = 1^k + 2^k + ... + n^k, which is
+///   p(n) = sum(C(k+1, j) * B_j * n^(k+1-j) for j in 0..=k) / (k+1)  with B_1 = +1/2
+pub fn faulhaber(k: usize) -> Polynomial<BigRational> {
+    let b = bernoulli_numbers(k);
+    let binomials = binomial_row(k + 1);
+    let mut coeffs = vec![BigRational::zero(); k + 2];
+    for (j, bj) in b.iter().enumerate() {
+        let bj = if j == 1 { -bj } else { bj.clone() };
+        let c = BigRational::from_integer(BigInt::from(binomials[j].clone()));
+        coeffs[k + 1 - j] = bj * c / BigInt::from(k + 1);
+    }
+    Polynomial::new(coeffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+    use crate::traits::ModInt;
+
+    #[test]
+    fn bernoulli_test() {
+        let b = bernoulli_numbers(20);
+        let r = |a: i64, c: i64| BigRational::new(BigInt::from(a), BigInt::from(c));
+        assert_eq!(b[..5], [r(1, 1), r(-1, 2), r(1, 6), r(0, 1), r(-1, 30)]);
+        assert_eq!(b[12], r(-691, 2730));
+        assert!(b.iter().skip(3).step_by(2).all(|x| x.is_zero()));
+        assert_eq!(bernoulli(20), r(-174611, 330));
+
+        // n(n + 1)(2n + 1) / 6
+        let f = faulhaber(2);
+        assert_eq!(f.coeffs(), [r(0, 1), r(1, 6), r(1, 2), r(1, 3)]);
+        let f = faulhaber(10);
+        let n = BigRational::from_integer(BigInt::from(1000000000000u64));
+        assert_eq!(f.eval(&n).to_integer(), BigInt::from(power_sum(1000000000000, 10)));
+    }
+
+    #[test]
+    fn power_sum_test() {
+        assert_eq!(power_sum(100, 3), BigUint::from(25502500u64));
+        assert_eq!(power_sum(100, 0), BigUint::from(100u64));
+        assert_eq!(power_sum(0, 5), BigUint::zero());
+        for &m in &[1000000000u64, 720, 1, 1000000007] {
+            for k in 0..12 {
+                for n in [0u64, 1, 5, 13, 100] {
+                    let expected = (1..=n).fold(0, |acc, i| acc.add_mod(i.pow_mod(k as u64, &m), &m));
+                    assert_eq!(power_sum_mod(n, k, m), expected);
+                }
+            }
+        }
+
+        let (n, k, m) = (123456789012345u64, 50, 1u64 << 40);
+        assert_eq!(power_sum_mod(n, k, m), (power_sum(n, k) % m).to_u64().unwrap());
+    }
+}
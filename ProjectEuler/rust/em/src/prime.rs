@@ -1,13 +1,21 @@
-/// Prime related functionalities
+//! Prime related functionalities
 // XXX: implement streaming prime sieve, like `primal` crate
 
-use std::collections::{HashMap};
+use std::collections::{HashMap, BinaryHeap};
+use std::cmp::Reverse;
 use bitvec::prelude::bitvec;
-use num_traits::{ToPrimitive, One, Pow};
-use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Signed, Zero, One, Pow};
+use num_bigint::{BigInt, BigUint};
 use num_integer::Integer;
 use rand::{random, seq::IteratorRandom};
-use crate::traits::Arithmetic;
+use crate::traits::{Arithmetic, ModInt};
+use crate::modroot::{sqrt_mod_prime, sqrt_mod_prime_power, nth_root_mod_prime, CrtRoots};
+use crate::dlog::{pohlig_hellman, DiscreteLog};
+use crate::int64::{crt, geometric_sum_mod};
+use crate::modcomb::BinomialMod;
+use crate::fibonacci::fibonacci_pair_mod;
+use crate::floor::{quotient_values, QuotientBlocks};
+use crate::powersum::power_sum_mod;
 
 pub struct PrimeBuffer {
     list: Vec<u64>, // list of found prime numbers
@@ -45,15 +53,15 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
 
         // Then do a deterministic Miller test
         let max_a = match target {  // https://oeis.org/A014233
-            0..=2047 => 2,
-            2048..=1373653 => 3,
-            1373654..=25326001 => 5,
-            25326002..=3251031751 => 7,
-            3251031752..=2152302898747 => 11,
-            2152302898748..=3474749660383 => 13,
-            3474749660384..=341550071728321 => 17,
-            341550071728322..=3825123056546413051 => 23,
-            3825123056546413052.. => 37
+            0..=2046 => 2,
+            2047..=1373652 => 3,
+            1373653..=25326000 => 5,
+            25326001..=3215031750 => 7,
+            3215031751..=2152302898746 => 11,
+            2152302898747..=3474749660382 => 13,
+            3474749660383..=341550071728320 => 17,
+            341550071728321..=3825123056546413050 => 23,
+            3825123056546413051.. => 37
         };
 
         self.list.iter()
@@ -105,6 +113,9 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
 
     /// Return list of found factors if not fully factored
     /// `trials` determines the maximum Pollard rho trials for each component
+    ///
+    /// Prime factors above 2^64 are only known as probable primes, so they are not accepted
+    /// and the target is reported as not fully factored as well.
     pub fn bfactors(&mut self, target: &BigUint, trials: Option<i32>) -> Result<HashMap<BigUint, usize>, Vec<BigUint>> {
         // if the target is in u64 range
         if let Some(x) = target.to_u64() {
@@ -144,7 +155,7 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
             }
             Ok(result)
         } else {
-            Err(trivial.into_keys().map(|x| BigUint::from(x)).chain(divided.into_keys()).collect())
+            Err(trivial.into_keys().map(BigUint::from).chain(divided.into_keys()).collect())
         }
     }
 
@@ -154,7 +165,7 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
         let mut residual = target;
         let mut result = HashMap::new();
         for &p in self.primes(num_integer::sqrt(target) + 1) {
-            while residual % p == 0 {
+            while residual.is_multiple_of(p) {
                 residual /= p;
                 *result.entry(p).or_insert(0) += 1;
             }
@@ -182,21 +193,23 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
         f1
     }
 
-    /// Find the factors by dividing the target by a proper divider, if the target is prime
-    /// or no divider is found, then an empty map is returned.
+    /// Find the factors by dividing the target by a proper divider recursively. Only the
+    /// certain primes are collected, a probable prime or a part without found divider is
+    /// left out.
     ///
     /// Note: 
     /// We don't factorize probable prime since it will takes a long time.
     /// To factorize a probable prime, use bdivisor
     fn bfactors_divide(&mut self, target: &BigUint, trials: i32) -> HashMap<BigUint, usize> {
-        if matches! (self.is_bprime(target, None), Primality::Yes | Primality::Probable(_)) {
-            return HashMap::new();
+        match self.is_bprime(target, None) {
+            Primality::Yes => return HashMap::from([(target.clone(), 1)]),
+            Primality::Probable(_) => return HashMap::new(),
+            Primality::No => {}
         }
 
         match self.bdivisor_rho(target, trials) {
             Some(d) => {
                 let mut f1 = self.bfactors_divide(&d, trials);
-                if f1.len() == 0 { f1.insert(d.clone(), 1); } // add divisor if it's a prime
                 let f2 = self.bfactors_divide(&(target / d), trials);
                 for (factor, exponent) in f2 {
                     *f1.entry(factor).or_insert(0) += exponent;
@@ -226,9 +239,7 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
     pub fn bdivisor(&mut self, target: &BigUint, trials: Option<i32>) -> Option<BigUint> {
         // if the target is in u64 range
         if let Some(x) = target.to_u64() {
-            return match self.divisor(x) {
-                Some(d) => Some(BigUint::from(d)), None => None
-            };
+            return self.divisor(x).map(BigUint::from);
         }
 
         let primality = self.is_bprime(target, None);
@@ -244,8 +255,8 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
     pub fn divisor_naive(&mut self, target: u64) -> u64 {
         debug_assert!(!self.is_prime(target));
         *self.primes(num_integer::sqrt(target) + 1).iter()
-            .filter(|&x| target % x == 0)
-            .next().unwrap()
+            .find(|&x| target.is_multiple_of(*x))
+            .unwrap()
     }
 
     // Get a factor using pollard_rho
@@ -305,7 +316,7 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
 
         // sieve with new primes
         for p in (current..num_integer::sqrt(odd_limit) + 1).step_by(2) {
-            if sieve[(p - current) as usize] {
+            if sieve[((p - current) / 2) as usize] {
                 continue;
             }
             for multi in (p*p .. odd_limit).step_by(2 * (p as usize)) {
@@ -336,6 +347,743 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
         }
     }
 
+    /// Returns all primes p with lo <= p < hi and p ≡ a (mod q). The primes are sorted.
+    ///
+    /// Only the residue class is sieved, so the cost is proportional to (hi - lo) / q
+    /// instead of (hi - lo).
+    pub fn primes_in_progression(&mut self, a: u64, q: u64, lo: u64, hi: u64) -> Vec<u64> {
+        assert!(q > 0);
+        let a = a % q;
+
+        // a class sharing a factor with q contains at most one prime
+        let g = a.gcd(&q);
+        if g > 1 {
+            return if lo <= g && g < hi && g % q == a && self.is_prime(g) { vec![g] } else { vec![] };
+        }
+
+        // first element of the class in range, the class contains first + k*q for k < size
+        let lo = lo.max(2);
+        let first = match lo.checked_add(a.sub_mod(lo % q, &q)) {
+            Some(first) if first < hi => first,
+            _ => return vec![]
+        };
+        let size = ((hi - first - 1) / q + 1) as usize;
+
+        let mut sieve = bitvec![0; size];
+        let root = num_integer::sqrt(hi - 1);
+        for &p in self.primes(root + 1) {
+            if q.is_multiple_of(p) {
+                continue; // no element in the class is divisible by p
+            }
+
+            // solve first + k*q ≡ 0 (mod p) with q^-1 = q^(p-2) (mod p)
+            let qinv = (q % p).pow_mod(p - 2, &p);
+            let k = (p - first % p).mul_mod(qinv, &p);
+
+            // skip the prime itself and start from p^2, in u128 since p*q can exceed the window
+            let (mut start, multi, square) = (k as u128, first as u128 + k as u128 * q as u128, p as u128 * p as u128);
+            if multi < square {
+                start += (square - multi).div_ceil(p as u128 * q as u128) * p as u128;
+            }
+            if start >= size as u128 {
+                continue;
+            }
+            for idx in (start as usize..size).step_by(p as usize) {
+                sieve.set(idx, true);
+            }
+        }
+
+        sieve.iter_zeros().map(|k| first + (k as u64) * q).collect()
+    }
+
+    /// Returns the number of primes not larger than x, using Lucy Hedgehog's method
+    pub fn pi(&mut self, x: u64) -> u64 {
+        self.pi_residues(x, 1)[0]
+    }
+
+    /// Returns the number of primes p not larger than x with p ≡ a (mod q)
+    pub fn pi_progression(&mut self, x: u64, q: u64, a: u64) -> u64 {
+        self.pi_residues(x, q)[(a % q) as usize]
+    }
+
+    /// Returns the number of primes not larger than x in each residue class modulo q.
+    /// The i-th element is the count of primes p <= x with p ≡ i (mod q).
+    ///
+    /// This is Lucy Hedgehog's prime counting with the counts split by residue classes,
+    /// which is the exact integer form of splitting by Dirichlet characters modulo q.
+    /// It takes O(q * x^(3/4)) time and O(q * sqrt(x)) memory.
+    pub fn pi_residues(&mut self, x: u64, q: u64) -> Vec<u64> {
+        let qs = q as usize;
+        let (_, large) = self.pi_table(x, q);
+        if x == 0 { vec![0; qs] } else { large[qs..2 * qs].to_vec() }
+    }
+
+    /// Returns sum(p^k for primes p <= v) mod m for each k in exps, at every v in
+    /// `quotient_values(n)`. This is Lucy Hedgehog's method with the counts weighted by p^k.
+    fn prime_power_sums(&mut self, n: u64, exps: &[usize], m: u64) -> Vec<Vec<u64>> {
+        let values = quotient_values(n);
+        let r = num_integer::sqrt(n);
+        let index = |v: u64| if v <= r { v as usize - 1 } else { values.len() - (n / v) as usize };
+
+        // start with the sums over 2..=v, then remove the multiples of each prime
+        let mut sums: Vec<Vec<u64>> = exps.iter().map(|&k| {
+            values.iter().map(|&v| power_sum_mod(v, k, m).sub_mod(1, &m)).collect()
+        }).collect();
+        for &p in self.primes(r + 1) {
+            for (&k, g) in exps.iter().zip(sums.iter_mut()) {
+                let pk = p.pow_mod(k as u64, &m);
+                let below = g[p as usize - 2]; // the sum up to p - 1
+                for i in (0..values.len()).rev() {
+                    let v = values[i];
+                    if v < p * p {
+                        break;
+                    }
+                    let d = g[index(v / p)].sub_mod(below, &m);
+                    g[i] = g[i].sub_mod(pk.mul_mod(d, &m), &m);
+                }
+            }
+        }
+        sums
+    }
+
+    /// Returns the sum of p^k for the primes p not larger than n, modulo m
+    pub fn prime_sum_mod(&mut self, n: u64, k: usize, m: u64) -> u64 {
+        match self.prime_power_sums(n, &[k], m)[0].last() {
+            Some(&x) => x, None => 0
+        }
+    }
+
+    /// Returns f(1) + f(2) + ... + f(n) mod m for a multiplicative function f by Min_25's
+    /// sieve. The sums of f over primes come from `prime_power_sums`, then the composites
+    /// are added by recursing on the smallest prime factor. It takes roughly O(n^(3/4))
+    /// operations and O(sqrt(n)) memory.
+    pub fn multiplicative_sum(&mut self, n: u64, f: &Multiplicative, m: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        let r = num_integer::sqrt(n);
+        let exps: Vec<usize> = (0..f.prime_poly.len()).collect();
+        let sums = self.prime_power_sums(n, &exps, m);
+        let coeffs: Vec<u64> = f.prime_poly.iter().map(|&c| (c as i128).rem_euclid(m as i128) as u64).collect();
+        let values = quotient_values(n);
+        let prime_sums = (0..values.len()).map(|i| {
+            coeffs.iter().zip(&sums).fold(0, |acc, (c, g)| acc.add_mod(c.mul_mod(g[i], &m), &m))
+        }).collect();
+
+        let primes = self.primes(r + 1).to_vec();
+        let mut prime_prefix = vec![0];
+        for &p in &primes {
+            let last = prime_prefix[prime_prefix.len() - 1];
+            prime_prefix.push(f.value(p, 1, m).add_mod(last, &m));
+        }
+        let sieve = Min25 { n, r, m, f, values, prime_sums, primes, prime_prefix };
+        sieve.sum(n, 0).add_mod(1, &m)
+    }
+
+    /// Returns the per-residue prime counts of Lucy Hedgehog's method for all values x/i.
+    /// The first table holds the counts of v <= sqrt(x) at [v*q, (v+1)*q), the second
+    /// holds the counts of x/i for i <= sqrt(x) at [i*q, (i+1)*q).
+    fn pi_table(&mut self, x: u64, q: u64) -> (Vec<u64>, Vec<u64>) {
+        assert!(q > 0);
+        let qs = q as usize;
+        let r = num_integer::sqrt(x);
+
+        // small[v*q + c] counts for v <= r, large[i*q + c] counts for x/i with i <= r
+        let mut small = vec![0u64; (r as usize + 1) * qs];
+        let mut large = vec![0u64; (r as usize + 1) * qs];
+
+        // initialize with the count of n in [2, v] with n ≡ c (mod q)
+        let init = |counts: &mut [u64], v: u64| {
+            for (c, count) in counts.iter_mut().enumerate() {
+                let c = c as u64;
+                *count = if v >= c { (v - c) / q + 1 } else { 0 };
+            }
+            counts[0] -= 1; // remove 0
+            if v >= 1 {
+                counts[(1 % q) as usize] -= 1; // remove 1
+            }
+        };
+        for v in 0..=r {
+            init(&mut small[v as usize * qs..(v as usize + 1) * qs], v);
+        }
+        for i in 1..=r {
+            init(&mut large[i as usize * qs..(i as usize + 1) * qs], x / i);
+        }
+
+        // remove the numbers whose least prime factor is p
+        let mut reduced = vec![0u64; qs];
+        for &p in self.primes(r + 1) {
+            let pidx = (p - 1) as usize * qs; // counts of primes below p
+            let pq = (p % q) as usize;
+            let pp = p * p;
+
+            for i in 1..=r {
+                if x / i < pp {
+                    break;
+                }
+                let ip = i * p;
+                let src = if ip <= r { &large[ip as usize * qs..] } else { &small[(x / ip) as usize * qs..] };
+                for (c, rc) in reduced.iter_mut().enumerate() {
+                    *rc = src[c] - small[pidx + c];
+                }
+                for (c, rc) in reduced.iter().enumerate() {
+                    large[i as usize * qs + pq * c % qs] -= rc;
+                }
+            }
+
+            for v in (pp..=r).rev() {
+                let sidx = (v / p) as usize * qs;
+                for (c, rc) in reduced.iter_mut().enumerate() {
+                    *rc = small[sidx + c] - small[pidx + c];
+                }
+                for (c, rc) in reduced.iter().enumerate() {
+                    small[v as usize * qs + pq * c % qs] -= rc;
+                }
+            }
+        }
+
+        (small, large)
+    }
+
+    /// Returns the factorization of every integer in [lo, hi), the i-th element
+    /// corresponds to lo + i. The window is sieved by the primes up to sqrt(hi), which
+    /// is much faster than calling `factors` on each integer.
+    pub fn factor_range(&mut self, lo: u64, hi: u64) -> Vec<HashMap<u64, usize>> {
+        assert!(lo > 0);
+        if lo >= hi {
+            return vec![];
+        }
+
+        let size = (hi - lo) as usize;
+        let mut residual: Vec<u64> = (lo..hi).collect();
+        let mut result = vec![HashMap::new(); size];
+        for &p in self.primes(num_integer::sqrt(hi - 1) + 1) {
+            let start = ((p - lo % p) % p) as usize;
+            for idx in (start..size).step_by(p as usize) {
+                let mut exponent = 0;
+                while residual[idx].is_multiple_of(p) {
+                    residual[idx] /= p;
+                    exponent += 1;
+                }
+                result[idx].insert(p, exponent);
+            }
+        }
+
+        // the residual has no factor below its square root, so it's a prime
+        for (factors, rest) in result.iter_mut().zip(residual) {
+            if rest > 1 {
+                factors.insert(rest, 1);
+            }
+        }
+        result
+    }
+
+    /// Returns the Möbius function μ(n) for all n below limit, μ(0) is set to 0
+    pub fn mobius(&mut self, limit: u64) -> Vec<i8> {
+        let size = limit as usize;
+        let mut mu = vec![1i8; size];
+        if size > 0 {
+            mu[0] = 0;
+        }
+        for &p in self.primes(limit) {
+            for m in (p as usize..size).step_by(p as usize) {
+                mu[m] = -mu[m];
+            }
+            if let Some(pp) = p.checked_mul(p) {
+                for m in (pp as usize..size).step_by(pp as usize) {
+                    mu[m] = 0;
+                }
+            }
+        }
+        mu
+    }
+
+    /// Returns the number of squarefree integers up to n, computed by
+    /// sum(μ(d) * floor(n / d^2)) for d up to sqrt(n)
+    pub fn count_squarefree(&mut self, n: u64) -> u64 {
+        let r = num_integer::sqrt(n);
+        let mu = self.mobius(r + 1);
+        let mut count: i64 = 0;
+        for d in 1..=r {
+            match mu[d as usize] {
+                1 => count += (n / (d * d)) as i64,
+                -1 => count -= (n / (d * d)) as i64,
+                _ => {}
+            }
+        }
+        count as u64
+    }
+
+    /// Returns the number of integers up to n with exactly k prime factors (counted
+    /// with multiplicity). k = 2 counts the semiprimes.
+    pub fn count_almost_primes(&mut self, n: u64, k: u32) -> u64 {
+        match k {
+            0 => return (n >= 1) as u64,
+            1 => return self.pi(n),
+            _ => {}
+        }
+
+        // prime counts on all values n/m from the Lucy Hedgehog's table
+        let (small, large) = self.pi_table(n, 1);
+        let r = num_integer::sqrt(n);
+        let pi = |v: u64| if v <= r { small[v as usize] } else { large[(n / v) as usize] };
+
+        // count the numbers up to m with k prime factors, all of them not less than primes[i]
+        fn count(m: u64, k: u32, i: usize, primes: &[u64], pi: &dyn Fn(u64) -> u64) -> u64 {
+            if k == 1 {
+                return pi(m).saturating_sub(i as u64);
+            }
+            let mut total = 0;
+            for (j, &p) in primes.iter().enumerate().skip(i) {
+                match p.checked_pow(k) {
+                    Some(pk) if pk <= m => total += count(m / p, k - 1, j, primes, pi),
+                    _ => break
+                }
+            }
+            total
+        }
+
+        let primes = self.primes(r + 1).to_vec();
+        count(n, k, 0, &primes, &pi)
+    }
+
+    /// Returns an iterator over all solutions of x^2 ≡ a (mod n) in [0, n). The roots
+    /// modulo each prime power factor of n are combined by the Chinese remainder theorem.
+    pub fn sqrt_mod(&mut self, a: u64, n: u64) -> CrtRoots {
+        assert!(n > 0);
+        let factors = if n == 1 { HashMap::new() } else { self.factors(n) };
+        CrtRoots::new(factors.into_iter().map(|(p, e)| {
+            (sqrt_mod_prime_power(a, p, e as u32), p.pow(e as u32))
+        }).collect())
+    }
+
+    /// Returns an iterator over all solutions of x^k ≡ a (mod p) in increasing order,
+    /// p has to be a prime.
+    pub fn nth_root_mod(&mut self, a: u64, k: u64, p: u64) -> std::vec::IntoIter<u64> {
+        let d = k.gcd(&(p - 1));
+        let factors = if d <= 1 { HashMap::new() } else { self.factors(d) };
+        nth_root_mod_prime(a, k, p, &factors).into_iter()
+    }
+
+    /// Returns the factorization of Carmichael's function λ(n), which is the exponent of
+    /// the multiplicative group modulo n
+    fn lambda_factors(&mut self, n: u64) -> HashMap<u64, usize> {
+        let mut result = HashMap::new();
+        if n == 1 {
+            return result;
+        }
+        for (p, e) in self.factors(n) {
+            // λ(p^e) = p^(e-1) * (p-1), except that λ(2^e) = 2^(e-2) for e >= 3
+            let mut part = if p > 2 { self.factors(p - 1) } else { HashMap::new() };
+            let exp = if p == 2 && e >= 3 { e - 2 } else { e - 1 };
+            if exp > 0 {
+                part.insert(p, exp);
+            }
+            for (q, k) in part {
+                let entry = result.entry(q).or_insert(0);
+                *entry = (*entry).max(k);
+            }
+        }
+        result
+    }
+
+    /// Returns Carmichael's function λ(n), the smallest m such that a^m ≡ 1 (mod n) for
+    /// all a coprime to n
+    pub fn carmichael_lambda(&mut self, n: u64) -> u64 {
+        self.lambda_factors(n).into_iter().map(|(q, k)| q.pow(k as u32)).product()
+    }
+
+    /// Returns the multiplicative order of a modulo n, or None if a is not coprime to n
+    pub fn order(&mut self, a: u64, n: u64) -> Option<u64> {
+        assert!(n > 0);
+        if n == 1 {
+            return Some(1);
+        }
+        if a.gcd(&n) != 1 {
+            return None;
+        }
+
+        // remove the prime factors from λ(n) as long as a^t stays 1
+        let factors = self.lambda_factors(n);
+        let mut t: u64 = factors.iter().map(|(q, &k)| q.pow(k as u32)).product();
+        for (q, k) in factors {
+            for _ in 0..k {
+                if a.pow_mod(t / q, &n) != 1 {
+                    break;
+                }
+                t /= q;
+            }
+        }
+        Some(t)
+    }
+
+    /// Returns the smallest primitive root modulo n, or None if it doesn't exist. Primitive
+    /// roots only exist for n = 1, 2, 4, p^k and 2p^k where p is an odd prime.
+    pub fn primitive_root(&mut self, n: u64) -> Option<u64> {
+        assert!(n > 0);
+        match n {
+            1 => return Some(0),
+            2 => return Some(1),
+            4 => return Some(3),
+            _ if n.is_multiple_of(4) => return None,
+            _ => {}
+        }
+        let odd = n >> n.trailing_zeros();
+        if self.factors(odd).len() != 1 {
+            return None;
+        }
+
+        // the group is cyclic, so g is a generator iff g^(λ/q) != 1 for every prime q | λ
+        let factors = self.lambda_factors(n);
+        let lambda: u64 = factors.iter().map(|(q, &k)| q.pow(k as u32)).product();
+        (2..n).find(|g| g.gcd(&n) == 1 && factors.keys().all(|q| g.pow_mod(lambda / q, &n) != 1))
+    }
+
+    /// Same as `bfactors`, except that a target which is a (probable) prime itself is accepted
+    fn bfactors_or_prime(&mut self, target: &BigUint, trials: Option<i32>) -> Result<HashMap<BigUint, usize>, Vec<BigUint>> {
+        if matches!(self.is_bprime(target, None), Primality::Yes | Primality::Probable(_)) {
+            return Ok(HashMap::from([(target.clone(), 1)]));
+        }
+        self.bfactors(target, trials)
+    }
+
+    /// Big integer version of `lambda_factors`, return list of found factors if n or any
+    /// p - 1 is not fully factored
+    fn blambda_factors(&mut self, n: &BigUint, trials: Option<i32>) -> Result<HashMap<BigUint, usize>, Vec<BigUint>> {
+        let mut result = HashMap::new();
+        if n.is_one() {
+            return Ok(result);
+        }
+        let two = BigUint::from(2u8);
+        for (p, e) in self.bfactors_or_prime(n, trials)? {
+            let pm1 = &p - 1u8;
+            let mut part = if pm1 > BigUint::one() { self.bfactors(&pm1, trials)? } else { HashMap::new() };
+            let exp = if p == two && e >= 3 { e - 2 } else { e - 1 };
+            if exp > 0 {
+                part.insert(p, exp);
+            }
+            for (q, k) in part {
+                let entry = result.entry(q).or_insert(0);
+                *entry = (*entry).max(k);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Big integer version of `carmichael_lambda`
+    pub fn bcarmichael_lambda(&mut self, n: &BigUint, trials: Option<i32>) -> Result<BigUint, Vec<BigUint>> {
+        Ok(self.blambda_factors(n, trials)?.iter().map(|(q, &k)| Pow::pow(q, k)).product())
+    }
+
+    /// Big integer version of `order`
+    pub fn border(&mut self, a: &BigUint, n: &BigUint, trials: Option<i32>) -> Result<Option<BigUint>, Vec<BigUint>> {
+        assert!(!n.is_zero());
+        if n.is_one() {
+            return Ok(Some(BigUint::one()));
+        }
+        if !a.gcd(n).is_one() {
+            return Ok(None);
+        }
+
+        let factors = self.blambda_factors(n, trials)?;
+        let mut t: BigUint = factors.iter().map(|(q, &k)| Pow::pow(q, k)).product();
+        for (q, k) in factors {
+            for _ in 0..k {
+                let d = &t / &q;
+                if !a.pow_mod(&d, n).is_one() {
+                    break;
+                }
+                t = d;
+            }
+        }
+        Ok(Some(t))
+    }
+
+    /// Big integer version of `primitive_root`
+    pub fn bprimitive_root(&mut self, n: &BigUint, trials: Option<i32>) -> Result<Option<BigUint>, Vec<BigUint>> {
+        if let Some(x) = n.to_u64() {
+            return Ok(self.primitive_root(x).map(BigUint::from));
+        }
+        if n.is_multiple_of(&BigUint::from(4u8)) {
+            return Ok(None);
+        }
+        let odd = if n.is_even() { n >> 1 } else { n.clone() };
+        if self.bfactors_or_prime(&odd, trials)?.len() != 1 {
+            return Ok(None);
+        }
+
+        let factors = self.blambda_factors(n, trials)?;
+        let lambda: BigUint = factors.iter().map(|(q, &k)| Pow::pow(q, k)).product();
+        let exps: Vec<BigUint> = factors.keys().map(|q| &lambda / q).collect();
+        let mut g = BigUint::from(2u8);
+        loop {
+            if g.gcd(n).is_one() && exps.iter().all(|e| !(&g).pow_mod(e, n).is_one()) {
+                return Ok(Some(g));
+            }
+            g += 1u8;
+        }
+    }
+
+    /// Returns the power tower a↑↑k = a^(a^(...^a)) with k copies of a modulo m, where
+    /// a↑↑0 = 1 and 0^0 = 1.
+    ///
+    /// The exponent is reduced modulo λ(m), since a^x ≡ a^y (mod m) whenever x ≡ y (mod λ(m))
+    /// and both of them are at least the largest exponent in the factorization of m, even if
+    /// a is not coprime to m.
+    pub fn power_tower_mod(&mut self, a: u64, k: u64, m: u64) -> u64 {
+        assert!(m > 0);
+        if m == 1 {
+            return 0;
+        }
+        if k == 0 {
+            return 1;
+        }
+        match tower_below(a, k - 1, TOWER_THRESHOLD) {
+            Some(e) => a.pow_mod(e, &m),
+            None => {
+                let lambda = self.carmichael_lambda(m);
+                let e = self.power_tower_mod(a, k - 1, lambda);
+                let shift = TOWER_THRESHOLD.div_ceil(lambda) * lambda;
+                a.pow_mod(e, &m).mul_mod(a.pow_mod(shift, &m), &m)
+            }
+        }
+    }
+
+    /// Big integer version of `power_tower_mod`
+    pub fn bpower_tower_mod(&mut self, a: &BigUint, k: u64, m: &BigUint, trials: Option<i32>) -> Result<BigUint, Vec<BigUint>> {
+        assert!(!m.is_zero());
+        if m.is_one() {
+            return Ok(BigUint::zero());
+        }
+        if k == 0 {
+            return Ok(BigUint::one());
+        }
+        let threshold = m.bits(); // larger than the exponents in the factorization of m
+        Ok(match tower_below(a.to_u64().unwrap_or(u64::MAX), k - 1, threshold) {
+            Some(e) => a.pow_mod(BigUint::from(e), m),
+            None => {
+                let lambda = self.bcarmichael_lambda(m, trials)?;
+                let e = self.bpower_tower_mod(a, k - 1, &lambda, trials)?;
+                let shift = BigUint::from(threshold).div_ceil(&lambda) * &lambda;
+                a.pow_mod(e + shift, m)
+            }
+        })
+    }
+
+    /// Solve a^x ≡ b (mod n) for non-negative x. Returns None if there's no solution,
+    /// otherwise the solutions are described by the least one and a period.
+    ///
+    /// The prime powers of n sharing factors with a are handled by brute force, since a^x
+    /// modulo them reaches zero within a few steps. On the other prime powers a is invertible,
+    /// the congruences on x are solved by Pohlig-Hellman and then combined by CRT.
+    pub fn discrete_log(&mut self, a: u64, b: u64, n: u64) -> Option<DiscreteLog> {
+        assert!(n > 0);
+        let (a, b) = (a % n, b % n);
+
+        // split n into n1 = product of p^e with p | a, and the other prime powers
+        let factors = if n == 1 { HashMap::new() } else { self.factors(n) };
+        let mut n1 = 1;
+        let mut units = Vec::new();
+        for (p, e) in factors {
+            let pe = p.pow(e as u32);
+            if a.is_multiple_of(p) { n1 *= pe } else { units.push(pe) }
+        }
+
+        // a^x mod n1 are distinct for x < t, and they are zero for x >= t
+        let mut t = 0;
+        let mut power = 1 % n1;
+        while power != 0 {
+            if power == b % n1 {
+                return if a.pow_mod(t, &n) == b { Some(DiscreteLog { x0: t, period: 0 }) } else { None };
+            }
+            power = power.mul_mod(a, &n1);
+            t += 1;
+        }
+        if b % n1 != 0 {
+            return None;
+        }
+
+        // solve x ≡ x_i (mod ord_i) modulo the other prime powers
+        let mut congruences = Vec::with_capacity(units.len());
+        for pe in units {
+            let order = self.order(a, pe).unwrap();
+            let factors = if order == 1 { HashMap::new() } else { self.factors(order) };
+            congruences.push((pohlig_hellman(a, b, pe, order, &factors)?, order));
+        }
+        let (mut x0, period) = crt(&congruences)?;
+        if x0 < t {
+            x0 += (t - x0).div_ceil(period) * period;
+        }
+        Some(DiscreteLog { x0, period })
+    }
+
+    /// Returns C(n, k) mod m. Tables of size p^e are built for each prime power factor of m
+    /// up to `BINOMIAL_TABLE_LIMIT`, use `BinomialMod` directly for repeated queries. The
+    /// larger prime powers are computed without tables, see `PrimePowerBinomial`.
+    pub fn binomial_mod(&mut self, n: u64, k: u64, m: u64) -> u64 {
+        assert!(m > 0);
+        let factors = if m == 1 { HashMap::new() } else { self.factors(m) };
+        BinomialMod::new(&factors).binomial(n, k)
+    }
+
+    /// Returns the Pisano period π(m), the period of the Fibonacci numbers modulo m, or
+    /// None if it doesn't fit in u64. π(m) <= 6m, so it's always found for m < 2^64 / 6.
+    ///
+    /// π(p^e) divides p^(e-1) * π(p), and π(p) divides p - 1 if p ≡ ±1 (mod 10), or 2(p + 1)
+    /// if p ≡ ±3 (mod 10). The period is found by removing prime factors from these bounds.
+    pub fn pisano_period(&mut self, m: u64) -> Option<u64> {
+        assert!(m > 0);
+        let factors = if m == 1 { HashMap::new() } else { self.factors(m) };
+        let mut period = 1u128;
+        for (p, e) in factors {
+            let pe = p.pow(e as u32);
+
+            // the bound is factorized by parts since it can exceed u64
+            let (base, double) = match p {
+                2 => (3, false),
+                5 => (20, false),
+                _ if p % 10 == 1 || p % 10 == 9 => (p - 1, false),
+                _ => (p + 1, true)
+            };
+            let mut bound_factors = self.factors(base);
+            if double {
+                *bound_factors.entry(2).or_insert(0) += 1;
+            }
+            if e > 1 {
+                *bound_factors.entry(p).or_insert(0) += e - 1;
+            }
+
+            let mut t = base as u128 * (pe / p) as u128;
+            if double {
+                t *= 2;
+            }
+            for (q, k) in bound_factors {
+                for _ in 0..k {
+                    if fibonacci_pair_mod_wide(t / q as u128, pe) != (0, 1 % pe) {
+                        break;
+                    }
+                    t /= q as u128;
+                }
+            }
+            period = period.lcm(&t);
+        }
+        period.to_u64()
+    }
+
+    /// Returns an iterator of the `bound`-smooth numbers up to limit in increasing order
+    pub fn smooth_numbers(&mut self, bound: u64, limit: u64) -> SmoothNumbers {
+        SmoothNumbers::new(self.primes(bound + 1), limit)
+    }
+
+    /// Returns the number of `bound`-smooth numbers up to limit
+    pub fn count_smooth(&mut self, bound: u64, limit: u64) -> u64 {
+        count_smooth(limit, self.primes(bound + 1))
+    }
+
+    /// Test if all prime factors of a big integer are not larger than `bound`
+    pub fn is_bsmooth(&mut self, target: &BigUint, bound: u64) -> bool {
+        is_bsmooth(target, self.primes(bound + 1))
+    }
+
+    /// Returns all n in [lo, hi) such that a*n^2 + b*n + c is a prime. Linear polynomials
+    /// are supported with a = 0.
+    ///
+    /// The roots of the polynomial modulo each cached prime p are used to cross out the
+    /// positions divisible by p, the survivors are then verified. For values larger than
+    /// 2^64 the survivors are tested by `is_bprime`, so probable primes are reported as well.
+    pub fn poly_primes(&mut self, a: i64, b: i64, c: i64, lo: u64, hi: u64) -> Vec<u64> {
+        if lo >= hi {
+            return vec![];
+        }
+        let size = (hi - lo) as usize;
+
+        // survivors are verified anyway, so sieving beyond the window size doesn't pay off
+        let bound = poly_sieve_bound(a, b, c, lo, hi).min((hi - lo).max(1 << 10));
+
+        let mut sieve = bitvec![0; size];
+        for &p in self.primes(bound + 1) {
+            for r in quadratic_roots_mod(a, b, c, p) {
+                let start = ((r + p - lo % p) % p) as usize;
+                for idx in (start..size).step_by(p as usize) {
+                    sieve.set(idx, true);
+                }
+            }
+
+            // keep the positions where the value is the prime itself
+            for n in quadratic_solve(a, b, c - p as i64) {
+                if lo <= n && n < hi {
+                    sieve.set((n - lo) as usize, false);
+                }
+            }
+        }
+
+        let mut result = Vec::new();
+        for idx in sieve.iter_zeros() {
+            let n = lo + idx as u64;
+            let prime = match quadratic_value(a, b, c, n) {
+                Some(v) if v <= 1 => false,
+                Some(v) if v < (bound as i128 + 1) * (bound as i128 + 1) => true,
+                Some(v) if v <= u64::MAX as i128 => self.is_prime(v as u64),
+                Some(v) => !matches!(self.is_bprime(&BigUint::from(v as u128), None), Primality::No),
+                None => {
+                    let v = BigInt::from(a) * n * n + BigInt::from(b) * n + c;
+                    match v.to_biguint() {
+                        Some(v) => !matches!(self.is_bprime(&v, None), Primality::No),
+                        None => false
+                    }
+                }
+            };
+            if prime {
+                result.push(n);
+            }
+        }
+        result
+    }
+
+    /// Returns the factorization of a*n^2 + b*n + c for every n in [lo, hi). The values
+    /// have to be positive and fit in u64. The i-th element corresponds to n = lo + i.
+    pub fn poly_factors(&mut self, a: i64, b: i64, c: i64, lo: u64, hi: u64) -> Vec<HashMap<u64, usize>> {
+        if lo >= hi {
+            return vec![];
+        }
+        let size = (hi - lo) as usize;
+        let bound = poly_sieve_bound(a, b, c, lo, hi);
+
+        let mut residual: Vec<u64> = (lo..hi).map(|n| match quadratic_value(a, b, c, n) {
+            Some(v) if 0 < v && v <= u64::MAX as i128 => v as u64,
+            _ => panic!("polynomial value at {} is not a positive u64", n)
+        }).collect();
+        let mut result = vec![HashMap::new(); size];
+        for &p in self.primes(bound + 1) {
+            for r in quadratic_roots_mod(a, b, c, p) {
+                let start = ((r + p - lo % p) % p) as usize;
+                for idx in (start..size).step_by(p as usize) {
+                    let entry = result[idx].entry(p).or_insert(0);
+                    while residual[idx].is_multiple_of(p) {
+                        residual[idx] /= p;
+                        *entry += 1;
+                    }
+                }
+            }
+        }
+
+        // the residual is either a prime or needs further factorization
+        let covered = (bound as u128 + 1) * (bound as u128 + 1);
+        for (factors, &rest) in result.iter_mut().zip(residual.iter()) {
+            if rest == 1 {
+                continue;
+            }
+            if (rest as u128) < covered {
+                *factors.entry(rest).or_insert(0) += 1;
+            } else {
+                for (p, e) in self.factors(rest) {
+                    *factors.entry(p).or_insert(0) += e;
+                }
+            }
+        }
+        result
+    }
+
     pub fn clear(&mut self) {
         self.list.truncate(12); // reserve 2 ~ 37 for miller test
         self.list.shrink_to_fit();
@@ -343,6 +1091,318 @@ impl PrimeBuffer { // TODO: support indexing and iterating to minimize python <-
     }
 }
 
+impl Default for PrimeBuffer {
+    #[inline]
+    fn default() -> Self { Self::new() }
+}
+
+/// Iterator of the numbers up to a limit whose prime factors are all in a given set,
+/// yielded in increasing order.
+pub struct SmoothNumbers {
+    primes: Vec<u64>,
+    limit: u64,
+    heap: BinaryHeap<Reverse<(u64, usize)>> // (number, index of its largest prime factor)
+}
+
+impl SmoothNumbers {
+    /// Create the iterator from a sorted list of primes
+    pub fn new(primes: &[u64], limit: u64) -> Self {
+        let mut heap = BinaryHeap::new();
+        if limit >= 1 {
+            heap.push(Reverse((1, 0)));
+        }
+        SmoothNumbers { primes: primes.to_vec(), limit, heap }
+    }
+}
+
+impl Iterator for SmoothNumbers {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let Reverse((n, i)) = self.heap.pop()?;
+
+        // only multiply by primes not smaller than the largest factor, so that each
+        // number is generated exactly once
+        for (j, &p) in self.primes.iter().enumerate().skip(i) {
+            match n.checked_mul(p) {
+                Some(m) if m <= self.limit => self.heap.push(Reverse((m, j))),
+                _ => break
+            }
+        }
+        Some(n)
+    }
+}
+
+/// A multiplicative function for `PrimeBuffer::multiplicative_sum`, described by f(p) as a
+/// polynomial in p and by f(p^e) for e >= 2. All the values are taken modulo m.
+pub struct Multiplicative {
+    prime_poly: Vec<i64>,
+    prime_power: Box<dyn Fn(u64, u32, u64) -> u64>
+}
+
+impl Multiplicative {
+    /// Create from the coefficients [c_0, c_1, ...] of f(p) = c_0 + c_1 p + ... and the
+    /// function (p, e, m) -> f(p^e) mod m for e >= 2
+    pub fn new<F: Fn(u64, u32, u64) -> u64 + 'static>(prime_poly: Vec<i64>, prime_power: F) -> Self {
+        Multiplicative { prime_poly, prime_power: Box::new(prime_power) }
+    }
+
+    /// Euler's totient function φ(n)
+    pub fn euler_phi() -> Self {
+        Self::new(vec![-1, 1], |p, e, m| p.pow_mod(e as u64 - 1, &m).mul_mod(p - 1, &m))
+    }
+
+    /// The Möbius function μ(n)
+    pub fn mobius() -> Self {
+        Self::new(vec![-1], |_, _, _| 0)
+    }
+
+    /// The divisor function σ_k(n) = sum(d^k for d | n), σ_0(n) is the number of divisors
+    pub fn divisor_sigma(k: usize) -> Self {
+        let mut prime_poly = vec![0; k + 1];
+        prime_poly[0] += 1;
+        prime_poly[k] += 1;
+        Self::new(prime_poly, move |p, e, m| geometric_sum_mod(p.pow_mod(k as u64, &m), e as u64 + 1, m))
+    }
+
+    /// Returns f(p^e) mod m
+    fn value(&self, p: u64, e: u32, m: u64) -> u64 {
+        if e == 1 {
+            self.prime_poly.iter().rev().fold(0, |acc, &c| {
+                acc.mul_mod(p, &m).add_mod((c as i128).rem_euclid(m as i128) as u64, &m)
+            })
+        } else {
+            (self.prime_power)(p, e, m)
+        }
+    }
+}
+
+/// The state of Min_25's sieve for `PrimeBuffer::multiplicative_sum`
+struct Min25<'a> {
+    n: u64,
+    r: u64, // floor(sqrt(n))
+    m: u64,
+    f: &'a Multiplicative,
+    values: Vec<u64>, // quotient_values(n)
+    prime_sums: Vec<u64>, // sum(f(p) for p <= v) for each v in values
+    primes: Vec<u64>, // primes up to sqrt(n)
+    prime_prefix: Vec<u64> // sum(f(p_i) for i < j)
+}
+
+impl Min25<'_> {
+    fn index(&self, v: u64) -> usize {
+        if v <= self.r { v as usize - 1 } else { self.values.len() - (self.n / v) as usize }
+    }
+
+    /// Returns the sum of f(i) for 2 <= i <= v whose smallest prime factor is at least p_j.
+    /// It's always called with v >= p_(j-1).
+    fn sum(&self, v: u64, j: usize) -> u64 {
+        let m = self.m;
+        let mut result = self.prime_sums[self.index(v)].sub_mod(self.prime_prefix[j], &m);
+        for (i, &p) in self.primes.iter().enumerate().skip(j) {
+            if p * p > v {
+                break;
+            }
+            // the numbers p^e * t with lpf(t) > p, and the prime powers p^(e+1)
+            let (mut pe, mut e) = (p, 1);
+            while pe <= v / p {
+                let term = self.f.value(p, e, m).mul_mod(self.sum(v / pe, i + 1), &m);
+                result = result.add_mod(term, &m).add_mod(self.f.value(p, e + 1, m), &m);
+                pe *= p;
+                e += 1;
+            }
+        }
+        result
+    }
+}
+
+/// Returns F(n) = f(1) + ... + f(n) mod m by Du's sieve. It needs a function g with g(1) = 1
+/// such that the prefix sums of g (`g_sum`) and of the Dirichlet convolution h = f * g
+/// (`h_sum`) are easy to compute, and the precomputed F(v) = small[v] for v < small.len().
+/// The closures and the small values don't need to be reduced modulo m.
+///
+/// Since H(v) = sum(g(i) * F(v / i) for i <= v), F(v) is H(v) minus the terms with i >= 2,
+/// which are grouped by the values of v / i. With small values up to n^(2/3), it takes
+/// O(n^(2/3)) operations.
+pub fn du_sieve<G: Fn(u64) -> u64, H: Fn(u64) -> u64>(n: u64, small: &[u64], g_sum: G, h_sum: H, m: u64) -> u64 {
+    fn prefix<G: Fn(u64) -> u64, H: Fn(u64) -> u64>(v: u64, small: &[u64], g_sum: &G, h_sum: &H, m: u64, memo: &mut HashMap<u64, u64>) -> u64 {
+        if (v as usize) < small.len() {
+            return small[v as usize] % m;
+        }
+        if let Some(&x) = memo.get(&v) {
+            return x;
+        }
+        let mut result = h_sum(v) % m;
+        for (q, range) in QuotientBlocks::new(v).skip(1) {
+            let weight = (g_sum(*range.end()) % m).sub_mod(g_sum(range.start() - 1) % m, &m);
+            result = result.sub_mod(weight.mul_mod(prefix(q, small, g_sum, h_sum, m, memo), &m), &m);
+        }
+        memo.insert(v, result);
+        result
+    }
+    prefix(n, small, &g_sum, &h_sum, m, &mut HashMap::new())
+}
+
+/// Exponents of this size exceed the multiplicity of any prime in a u64 modulus
+const TOWER_THRESHOLD: u64 = 64;
+
+/// Returns the power tower a↑↑k if it's less than the bound
+fn tower_below(a: u64, k: u64, bound: u64) -> Option<u64> {
+    match a {
+        0 => return Some((k + 1) % 2), // 0↑↑k alternates between 1 and 0
+        1 => return Some(1),
+        _ => {}
+    }
+    let mut value = 1;
+    for _ in 0..k {
+        if value >= 64 {
+            return None; // a^value >= 2^64
+        }
+        value = a.checked_pow(value as u32).filter(|&x| x < bound)?;
+    }
+    Some(value)
+}
+
+/// Returns (F(n), F(n+1)) mod m for n beyond u64 by the addition formula
+/// F(a+b) = F(a)F(b+1) + F(a+1)F(b) - F(a)F(b)
+fn fibonacci_pair_mod_wide(mut n: u128, m: u64) -> (u64, u64) {
+    let (mut a, mut b) = (0, 1 % m);
+    while n > 0 {
+        let step = n.min(u64::MAX as u128);
+        let (c, d) = fibonacci_pair_mod(step as u64, m);
+        let ac = a.mul_mod(c, &m);
+        let next = a.mul_mod(d, &m).add_mod(b.mul_mod(c, &m), &m).sub_mod(ac, &m);
+        b = b.mul_mod(d, &m).add_mod(ac, &m);
+        a = next;
+        n -= step;
+    }
+    (a, b)
+}
+
+/// Returns the number of integers up to limit whose prime factors are all in `primes`.
+/// The primes should be sorted.
+///
+/// The numbers are split by their smallest prime factor, and the ones whose smallest prime
+/// factor exceeds the square root of the remaining limit are primes, which are counted in
+/// the list directly. The recursion depth is at most log2(limit).
+pub fn count_smooth(limit: u64, primes: &[u64]) -> u64 {
+    if limit == 0 {
+        return 0;
+    }
+    let primes = &primes[..primes.partition_point(|&p| p <= limit)];
+    count_smooth_from(limit, primes, 0)
+}
+
+/// Count 1 and the numbers up to limit with all prime factors in primes[start..]
+fn count_smooth_from(limit: u64, primes: &[u64], start: usize) -> u64 {
+    let mut count = 1;
+    for (j, &p) in primes.iter().enumerate().skip(start) {
+        if p > limit / p {
+            // the numbers with smallest prime factor p_j, ... can only be primes
+            return count + (primes.partition_point(|&q| q <= limit) - j) as u64;
+        }
+        count += count_smooth_from(limit / p, primes, j);
+    }
+    count
+}
+
+/// Test if a big integer factors completely over `primes`, by trial division only
+pub fn is_bsmooth(target: &BigUint, primes: &[u64]) -> bool {
+    assert!(!target.is_zero());
+
+    let mut residual = target.clone();
+    for &p in primes {
+        if residual.is_one() {
+            break;
+        }
+        while residual.is_multiple_of(&BigUint::from(p)) {
+            residual /= p;
+        }
+    }
+    residual.is_one()
+}
+
+/// Primes below this limit are used to sieve polynomial values
+const POLY_SIEVE_LIMIT: u64 = 1 << 27;
+
+/// Returns a*n^2 + b*n + c, or None if it overflows
+fn quadratic_value(a: i64, b: i64, c: i64, n: u64) -> Option<i128> {
+    let n = n as i128;
+    let an2 = (a as i128).checked_mul(n)?.checked_mul(n)?;
+    an2.checked_add((b as i128).checked_mul(n)?)?.checked_add(c as i128)
+}
+
+/// Returns the largest prime needed to sieve the polynomial values on [lo, hi)
+fn poly_sieve_bound(a: i64, b: i64, c: i64, lo: u64, hi: u64) -> u64 {
+    let mut candidates = vec![lo, hi - 1];
+    if a < 0 { // the maximum of a concave polynomial can be at the vertex
+        let vertex = (-b as f64 / (2. * a as f64)).max(0.) as u64;
+        candidates.push(vertex.clamp(lo, hi - 1));
+    }
+
+    let max = candidates.into_iter()
+        .map(|n| (BigInt::from(a) * n * n + BigInt::from(b) * n + c).abs())
+        .max().unwrap();
+    match max.sqrt().to_u64() {
+        Some(s) if s < POLY_SIEVE_LIMIT => s,
+        _ => POLY_SIEVE_LIMIT
+    }
+}
+
+/// Returns all non-negative integers n with a*n^2 + b*n + c = 0
+fn quadratic_solve(a: i64, b: i64, c: i64) -> Vec<u64> {
+    let candidates = if a == 0 {
+        if b == 0 || c % b != 0 { return vec![] }
+        vec![-c / b]
+    } else {
+        let disc = (b as f64) * (b as f64) - 4. * (a as f64) * (c as f64);
+        if disc < 0. { return vec![] }
+        let s = disc.sqrt();
+        let r1 = ((-b as f64 - s) / (2. * a as f64)).round() as i64;
+        let r2 = ((-b as f64 + s) / (2. * a as f64)).round() as i64;
+        (r1 - 1..=r1 + 1).chain(r2 - 1..=r2 + 1).collect()
+    };
+    let mut roots: Vec<u64> = candidates.into_iter()
+        .filter(|&n| n >= 0 && quadratic_value(a, b, c, n as u64) == Some(0))
+        .map(|n| n as u64)
+        .collect();
+    roots.sort_unstable();
+    roots.dedup();
+    roots
+}
+
+/// Returns the roots of a*n^2 + b*n + c modulo a prime p, sorted and deduplicated
+fn quadratic_roots_mod(a: i64, b: i64, c: i64, p: u64) -> Vec<u64> {
+    let pi = p as i128;
+    let (a, b, c) = [a, b, c].map(|x| (x as i128).rem_euclid(pi) as u64).into();
+    let inv = |x: u64| x.pow_mod(p - 2, &p);
+
+    if p == 2 {
+        return (0..2).filter(|&n| (a * n * n + b * n + c) % 2 == 0).collect();
+    }
+    if a == 0 {
+        return match (b, c) {
+            (0, 0) => (0..p).collect(),
+            (0, _) => vec![],
+            _ => vec![(p - c).mul_mod(inv(b), &p)]
+        };
+    }
+
+    // n = (-b ± sqrt(b^2 - 4ac)) / 2a
+    let disc = (b.mul_mod(b, &p) + p - (4 * a % p).mul_mod(c, &p)) % p;
+    let s = match sqrt_mod_prime(disc, p) {
+        Some(s) => s, None => return vec![]
+    };
+    let inv2a = inv(2 * a % p);
+    let mut roots = vec![
+        ((p - b + s) % p).mul_mod(inv2a, &p),
+        ((2 * p - b - s) % p).mul_mod(inv2a, &p)
+    ];
+    roots.sort_unstable();
+    roots.dedup();
+    roots
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,12 +1415,20 @@ mod tests {
         let prime100 = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97];
         assert_eq!(pb.primes(50), prime50);
         assert_eq!(pb.primes(100), prime100);
+        assert_eq!(pb.primes(10000).len(), 1229);
+
+        // sieving new primes from the initial buffer, 43^2 must not be reported
+        assert!(!PrimeBuffer::new().primes(2000).contains(&1849));
+        assert_eq!(PrimeBuffer::new().primes(100000).len(), 9592);
     }
     
     #[test]
     fn prime_assertion_test() {
         let mut pb = PrimeBuffer::new();
         assert!(pb.is_prime(6469693333));
+        for spsp in [2047, 1373653, 25326001, 3215031751, 2152302898747, 3474749660383, 341550071728321, 3825123056546413051] {
+            assert!(!pb.is_prime(spsp));
+        }
         let prime100 = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97];
         for x in 2..100 {
             assert_eq!(prime100.contains(&x), pb.is_prime(x));
@@ -375,6 +1443,15 @@ mod tests {
         assert!(matches!(pb.is_bprime(&BigUint::from(2u32.pow(23) - 1), None), Primality::No));
         let m89 = BigUint::from(2u8).pow(89usize) - 1u8;
         assert!(matches!(pb.is_bprime(&m89, None), Primality::Probable(_)));
+
+        // the first strong pseudoprimes to each set of witnesses in the table (A014233),
+        // and the Carmichael number 15841 which passes the Fermat test
+        let pb = PrimeBuffer::new();
+        for x in [2047, 1373653, 25326001, 3215031751, 2152302898747, 3474749660383,
+                  341550071728321, 3825123056546413051, 15841] {
+            assert!(!pb.is_prime(x));
+        }
+        assert!(pb.is_prime(18446744073709551557)); // the largest prime below 2^64
     }
 
     #[test]
@@ -386,7 +1463,327 @@ mod tests {
 
         let m131 = BigUint::from(2u8).pow(131usize) - 1u8; // m131/263 is a large prime
         let fac = pb.bfactors(&m131, None);
-        assert!(matches!(fac, Err(f) if f.len() > 0));
+        assert!(matches!(fac, Err(f) if !f.is_empty()));
+
+        // prime factors below 2^64 are certain, also as the cofactor after trial division
+        let m61 = BigUint::from((1u64 << 61) - 1);
+        let target = &m61 * 1000000007u32 * 8u8;
+        let fac = pb.bfactors(&target, None).ok().unwrap();
+        assert_eq!(fac, HashMap::from_iter([(m61.clone(), 1), (BigUint::from(1000000007u32), 1), (BigUint::from(2u8), 3)]));
+        let fac = pb.bfactors(&(&m61 << 10usize), None).ok().unwrap();
+        assert_eq!(fac, HashMap::from_iter([(m61, 1), (BigUint::from(2u8), 10)]));
+
+        // a probable prime factor is not accepted
+        let m89 = BigUint::from(2u8).pow(89usize) - 1u8;
+        let fac = pb.bfactors(&(m89 * 1000003u32), None);
+        assert!(matches!(fac, Err(f) if f == [BigUint::from(1000003u32)]));
     }
-}
 
+    #[test]
+    fn progression_test() {
+        let mut pb = PrimeBuffer::new();
+        let plist = pb.primes(100000).to_vec();
+        for &(a, q, lo, hi) in &[(1, 4, 0, 100000), (3, 4, 50, 99999), (7, 10, 1000, 5000), (0, 7, 0, 100), (2, 6, 0, 1000), (5, 30, 10000, 12345)] {
+            let expected: Vec<u64> = plist.iter().cloned()
+                .filter(|&p| p % q == a && lo <= p && p < hi).collect();
+            assert_eq!(pb.primes_in_progression(a, q, lo, hi), expected);
+        }
+
+        // the step p*q exceeds the window
+        let q = 10u64.pow(13);
+        let expected: Vec<u64> = (1..100).map(|k| 1 + k * q).filter(|&n| pb.is_prime(n)).collect();
+        assert_eq!(pb.primes_in_progression(1, q, 0, 10u64.pow(15)), expected);
+
+        assert_eq!(pb.pi(0), 0);
+        assert_eq!(pb.pi(2), 1);
+        assert_eq!(pb.pi(100000), plist.len() as u64);
+        assert_eq!(pb.pi(10u64.pow(9)), 50847534);
+        for x in [1, 10, 97, 1000, 65535, 99999] {
+            for q in [1, 3, 4, 10, 12] {
+                let counts = pb.pi_residues(x, q);
+                for a in 0..q {
+                    let expected = plist.iter().filter(|&&p| p <= x && p % q == a).count() as u64;
+                    assert_eq!(counts[a as usize], expected);
+                    assert_eq!(pb.pi_progression(x, q, a), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn poly_sieve_test() {
+        let mut pb = PrimeBuffer::new();
+        for &(a, b, c) in &[(2, 0, -1), (1, 0, 1), (1, 1, 41), (0, 6, 1), (3, -7, 5), (4, 0, 4)] {
+            let expected: Vec<u64> = (0..3000u64).filter(|&n| {
+                let v = a * (n * n) as i64 + b * n as i64 + c;
+                v > 1 && pb.is_prime(v as u64)
+            }).collect();
+            assert_eq!(pb.poly_primes(a, b, c, 0, 3000), expected);
+        }
+
+        // values above 2^64
+        let lo = 1u64 << 40;
+        let expected: Vec<u64> = (lo..lo + 500).filter(|&n| {
+            let v = BigUint::from(n) * n + 1u8;
+            !matches!(pb.is_bprime(&v, None), Primality::No)
+        }).collect();
+        assert_eq!(pb.poly_primes(1, 0, 1, lo, lo + 500), expected);
+
+        // primes below bound + 1 = 1031 are sieved, so 1031^2 has to be verified
+        let (lo, hi) = (1031 * 1031 - 500, 1031 * 1031 + 530);
+        let expected: Vec<u64> = (lo..hi).filter(|&n| pb.is_prime(n)).collect();
+        assert_eq!(pb.poly_primes(0, 1, 0, lo, hi), expected);
+
+        let facs = pb.poly_factors(2, 0, -1, 1000, 2000);
+        for (i, f) in facs.into_iter().enumerate() {
+            let n = 1000 + i as u64;
+            assert_eq!(f, pb.factors(2 * n * n - 1));
+        }
+    }
+
+    #[test]
+    fn factor_range_test() {
+        let mut pb = PrimeBuffer::new();
+        for &(lo, hi) in &[(1, 2000), (10u64.pow(12), 10u64.pow(12) + 2000), (u32::MAX as u64 - 1000, u32::MAX as u64 + 1000)] {
+            let facs = pb.factor_range(lo, hi);
+            assert_eq!(facs.len(), (hi - lo) as usize);
+            for (n, f) in (lo..hi).zip(facs) {
+                if n == 1 {
+                    assert!(f.is_empty());
+                } else {
+                    assert_eq!(f, pb.factors(n));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_test() {
+        let mut pb = PrimeBuffer::new();
+        for &(bound, limit) in &[(2, 1000), (5, 10000), (7, 1), (13, 20000), (100, 30000)] {
+            let expected: Vec<u64> = (1..=limit)
+                .filter(|&n| n == 1 || pb.factors(n).keys().all(|&p| p <= bound)).collect();
+            assert_eq!(pb.smooth_numbers(bound, limit).collect::<Vec<_>>(), expected);
+            assert_eq!(pb.count_smooth(bound, limit), expected.len() as u64);
+        }
+        assert_eq!(pb.count_smooth(100, 10u64.pow(9)), 2944730);
+        assert_eq!(count_smooth(243, &[3]), 6);
+        assert_eq!(count_smooth(242, &[3, 7]), SmoothNumbers::new(&[3, 7], 242).count() as u64);
+        assert_eq!(pb.count_smooth(3000000, 2000000), 2000000);
+
+        // an integer up to 10^7 has at most one prime factor above 10^6
+        let n = 10u64.pow(7);
+        let rough: u64 = pb.primes(n + 1).iter().filter(|&&p| p > 1000000).map(|&p| n / p).sum();
+        assert_eq!(pb.count_smooth(1000000, n), n - rough);
+
+        let smooth = BigUint::from(2u8).pow(100u32) * BigUint::from(97u8).pow(20u32);
+        assert!(pb.is_bsmooth(&smooth, 100));
+        assert!(!pb.is_bsmooth(&(smooth * 101u8), 100));
+    }
+
+    #[test]
+    fn squarefree_test() {
+        let mut pb = PrimeBuffer::new();
+        let omega: Vec<(bool, u32)> = (1..=20000u64).map(|n| {
+            let f = if n == 1 { HashMap::new() } else { pb.factors(n) };
+            (f.values().all(|&e| e == 1), f.values().map(|&e| e as u32).sum())
+        }).collect();
+
+        let mu = pb.mobius(1000);
+        for n in 1..1000 {
+            let (sqfree, count) = omega[n - 1];
+            let expected = if !sqfree { 0 } else if count % 2 == 0 { 1 } else { -1 };
+            assert_eq!(mu[n], expected);
+        }
+
+        for n in [1u64, 2, 10, 99, 1000, 4321, 20000] {
+            let expected = omega[..n as usize].iter().filter(|(s, _)| *s).count() as u64;
+            assert_eq!(pb.count_squarefree(n), expected);
+            for k in 0..6 {
+                let expected = omega[..n as usize].iter().filter(|(_, c)| *c == k).count() as u64;
+                assert_eq!(pb.count_almost_primes(n, k), expected);
+            }
+        }
+        assert_eq!(pb.count_almost_primes(10u64.pow(8) - 1, 2), 17427258);
+    }
+
+    #[test]
+    fn sqrt_mod_test() {
+        let mut pb = PrimeBuffer::new();
+        for n in 1..200u64 {
+            for a in 0..n {
+                let expected: Vec<u64> = (0..n).filter(|&x| x * x % n == a).collect();
+                let mut roots: Vec<u64> = pb.sqrt_mod(a, n).collect();
+                roots.sort_unstable();
+                assert_eq!(roots, expected);
+            }
+        }
+
+        let n = 1000000007 * 998244353;
+        let mut roots: Vec<u64> = pb.sqrt_mod(4, n).collect();
+        roots.sort_unstable();
+        assert_eq!(roots.len(), 4);
+        assert_eq!(roots[0], 2);
+        assert!(roots.iter().all(|r| r.mul_mod(r, &n) == 4));
+
+        // the 2^31 roots of 0 modulo 2^62 are generated lazily
+        let mut roots = pb.sqrt_mod(0, 1 << 62);
+        assert_eq!(roots.nth(3), Some(3 << 31));
+
+        // 998244353 - 1 = 2^23 * 7 * 17
+        let roots: Vec<u64> = pb.nth_root_mod(1, 14, 998244353).collect();
+        assert_eq!(roots.len(), 14);
+        assert!(roots.iter().all(|r| r.pow_mod(14, &998244353) == 1));
+    }
+
+    #[test]
+    fn order_test() {
+        let mut pb = PrimeBuffer::new();
+        for n in 1..300u64 {
+            let mut lambda = 1;
+            for a in 0..n {
+                let order = (1..=n).find(|&k| a.pow_mod(k, &n) == 1 % n);
+                let order = if a.gcd(&n) == 1 { order } else { None };
+                assert_eq!(pb.order(a, n), order);
+                if let Some(k) = order {
+                    lambda = lambda.lcm(&k);
+                }
+            }
+            assert_eq!(pb.carmichael_lambda(n), lambda);
+
+            let totient = (1..=n).filter(|a| a.gcd(&n) == 1).count() as u64;
+            let root = (0..n).find(|&g| pb.order(g, n) == Some(totient));
+            assert_eq!(pb.primitive_root(n), root);
+        }
+        assert_eq!(pb.primitive_root(1000000007), Some(5));
+        assert_eq!(pb.primitive_root(2 * 1000000007u64.pow(2)), Some(5));
+        assert_eq!(pb.order(2, 1000000007), Some(500000003));
+        assert_eq!(pb.order(10, 1000000007), Some(1000000006));
+
+        // 2^89 - 1 is a Mersenne prime
+        let m89 = BigUint::from(2u8).pow(89u32) - 1u8;
+        assert_eq!(pb.border(&BigUint::from(2u8), &m89, None), Ok(Some(BigUint::from(89u8))));
+        assert_eq!(pb.bcarmichael_lambda(&m89, None), Ok(&m89 - 1u8));
+        let g = pb.bprimitive_root(&m89, None).unwrap().unwrap();
+        assert_eq!(pb.border(&g, &m89, None), Ok(Some(&m89 - 1u8)));
+        assert_eq!(pb.bprimitive_root(&(&m89 * 4u8), None), Ok(None));
+    }
+
+    #[test]
+    fn discrete_log_test() {
+        let mut pb = PrimeBuffer::new();
+        for n in 1..50u64 {
+            for a in 0..n {
+                for b in 0..n {
+                    let solutions: Vec<u64> = (0..3 * n).filter(|&x| a.pow_mod(x, &n) == b % n).collect();
+                    match pb.discrete_log(a, b, n) {
+                        Some(log) => {
+                            assert_eq!(solutions[0], log.x0);
+                            assert!(solutions.iter().all(|&x| log.contains(x)));
+                            assert!((0..3 * n).filter(|&x| log.contains(x)).eq(solutions.iter().cloned()));
+                        },
+                        None => assert!(solutions.is_empty())
+                    }
+                }
+            }
+        }
+
+        let p = 1000000007u64;
+        let log = pb.discrete_log(5, 5u64.pow_mod(123456789, &p), p);
+        assert_eq!(log, Some(DiscreteLog { x0: 123456789, period: p - 1 }));
+        let n = 1u64 << 40;
+        assert_eq!(pb.discrete_log(6, 0, n), Some(DiscreteLog { x0: 40, period: 1 }));
+        assert_eq!(pb.discrete_log(2, 4, 3 * n), Some(DiscreteLog { x0: 2, period: 0 }));
+    }
+
+    #[test]
+    fn binomial_mod_test() {
+        let mut pb = PrimeBuffer::new();
+        assert_eq!(pb.binomial_mod(10, 3, 1), 0);
+        assert_eq!(pb.binomial_mod(30, 15, 1000003), 117055);
+        assert_eq!(pb.binomial_mod(30, 15, 1000000), 117520);
+        assert_eq!(pb.binomial_mod(1000000000000000000, 1, 142857), 1000000000000000000 % 142857);
+
+        // large prime and composite moduli
+        let p = 1000000007u64;
+        assert_eq!(pb.binomial_mod(10, 3, p), 120);
+        assert_eq!(pb.binomial_mod(1000000000000000000, 2, p), 1176);
+        assert_eq!(pb.binomial_mod(1000000000000, 3, p * 998244353), 104838140542698379);
+        assert_eq!(pb.binomial_mod(10, 3, 10007 * 10007), 120);
+    }
+
+    #[test]
+    fn pisano_test() {
+        let mut pb = PrimeBuffer::new();
+        for m in 1..500u64 {
+            let period = (1..).find(|&k| fibonacci_pair_mod(k, m) == (0, 1 % m)).unwrap();
+            assert_eq!(pb.pisano_period(m), Some(period));
+        }
+        assert_eq!(pb.pisano_period(1000000000), Some(1500000000));
+        assert_eq!(pb.pisano_period(1000000007), Some(2000000016));
+
+        // the bound 2(p + 1) exceeds u64
+        assert_eq!(pb.pisano_period(18446744073709551557), Some(5270498306774157588));
+        assert_eq!(pb.pisano_period(18446744073709551533), None);
+    }
+
+    #[test]
+    fn multiplicative_sum_test() {
+        let mut pb = PrimeBuffer::new();
+        let m = (1 << 61) - 1;
+        assert_eq!(pb.prime_sum_mod(1000000, 0, m), 78498);
+        assert_eq!(pb.prime_sum_mod(2000000, 1, m), 142913828922); // Project Euler 10
+        assert_eq!(pb.prime_sum_mod(1, 2, m), 0);
+
+        // compare with the values from factorizations
+        let functions = [Multiplicative::euler_phi(), Multiplicative::mobius(), Multiplicative::divisor_sigma(0), Multiplicative::divisor_sigma(2)];
+        let factors = pb.factor_range(1, 301);
+        for f in &functions {
+            let mut sum = 0;
+            for (n, fs) in (1..301u64).zip(&factors) {
+                let value = fs.iter().fold(1, |acc, (&p, &e)| acc.mul_mod(f.value(p, e as u32, m), &m));
+                sum = (&sum).add_mod(value, &m);
+                assert_eq!(pb.multiplicative_sum(n, f, m), sum);
+            }
+        }
+
+        // Project Euler 72, and the Mertens function M(10^6) = 212
+        let n = 1000000;
+        assert_eq!(pb.multiplicative_sum(n, &Multiplicative::euler_phi(), m), 303963552392);
+        assert_eq!(pb.multiplicative_sum(n, &Multiplicative::mobius(), m), 212);
+        let divisors: u64 = QuotientBlocks::new(1000000000).map(|(q, r)| q * (r.end() - r.start() + 1)).sum();
+        assert_eq!(pb.multiplicative_sum(1000000000, &Multiplicative::divisor_sigma(0), m), divisors);
+
+        // Du's sieve with μ * 1 = ε and φ * 1 = id
+        let mu = pb.mobius(10001);
+        let small: Vec<u64> = mu.iter().scan(0u64, |acc, &x| { *acc = acc.add_mod((x as i64).rem_euclid(m as i64) as u64, &m); Some(*acc) }).collect();
+        assert_eq!(du_sieve(n, &small, |v| v, |_| 1, m), 212);
+        let phi_small = [0, 1, 2, 4, 6, 10];
+        let triangle = |v: u64| ((v as u128) * (v as u128 + 1) / 2 % m as u128) as u64;
+        assert_eq!(du_sieve(n, &phi_small, |v| v, triangle, m), 303963552392);
+        let triangle = |v: u64| v * (v + 1) / 2;
+        assert_eq!(du_sieve(n, &phi_small, |v| v, triangle, 1009), 303963552392 % 1009);
+    }
+
+    #[test]
+    fn power_tower_test() {
+        let tower = |a: u64, k: u64| (0..k).fold(BigUint::one(), |acc, _| Pow::pow(BigUint::from(a), acc.to_u32().unwrap()));
+        let mut pb = PrimeBuffer::new();
+        for m in 1..200u64 {
+            for (a, k) in (0..20).map(|a| (a, 2)).chain((0..6).map(|a| (a, 3))).chain([(2, 4), (3, 1), (7, 0)]) {
+                let expected = (tower(a, k) % m).to_u64().unwrap();
+                assert_eq!(pb.power_tower_mod(a, k, m), expected);
+                assert_eq!(pb.bpower_tower_mod(&BigUint::from(a), k, &BigUint::from(m), None), Ok(BigUint::from(expected)));
+            }
+        }
+
+        // Project Euler 188
+        assert_eq!(pb.power_tower_mod(1777, 1855, 100000000), 95962097);
+        assert_eq!(pb.power_tower_mod(0, 1000000000000, 10), 1);
+        let m61 = (1 << 61) - 1;
+        assert_eq!(pb.power_tower_mod(2, u64::MAX, m61), pb.power_tower_mod(2, 100, m61));
+        let big = BigUint::from(10u8).pow(30u32);
+        let x = pb.bpower_tower_mod(&BigUint::from(1777u16), 1855, &big, None).unwrap();
+        assert_eq!(x % 100000000u32, BigUint::from(95962097u32));
+    }
+}
@@ -0,0 +1,197 @@
+//! Linear recurrences a_i = c_1 * a_(i-1) + c_2 * a_(i-2) + ... + c_d * a_(i-d)
+//!
+//! The coefficients are always given as [c_1, ..., c_d] and the initial terms as
+//! [a_0, ..., a_(d-1)].
+
+use num_bigint::BigUint;
+use crate::combinatorics::{Ring, Exact, Modular};
+use crate::traits::{ModInt, ModUnary};
+
+/// Multiply two polynomials of degree below d, then reduce the product modulo the
+/// characteristic polynomial x^d - c_1 * x^(d-1) - ... - c_d
+fn mul_reduce<R: Ring>(r: &R, a: &[R::Item], b: &[R::Item], coeffs: &[R::Item]) -> Vec<R::Item> {
+    let d = coeffs.len();
+    let mut prod = vec![r.num(0); 2 * d - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            prod[i + j] = r.add(&prod[i + j], &r.mul(x, y));
+        }
+    }
+
+    // x^k = c_1 * x^(k-1) + ... + c_d * x^(k-d)
+    for k in (d..2 * d - 1).rev() {
+        let t = prod[k].clone();
+        for (j, c) in coeffs.iter().enumerate() {
+            prod[k - j - 1] = r.add(&prod[k - j - 1], &r.mul(&t, c));
+        }
+    }
+    prod.truncate(d);
+    prod
+}
+
+/// Kitamasa's method: with x^n ≡ sum(r_i * x^i) modulo the characteristic polynomial,
+/// the n-th term is sum(r_i * a_i). It takes O(d^2 log n) operations.
+fn kitamasa<R: Ring>(r: &R, coeffs: &[R::Item], init: &[R::Item], n: u64) -> R::Item {
+    let d = coeffs.len();
+    assert_eq!(init.len(), d);
+    if n < d as u64 {
+        return init[n as usize].clone();
+    }
+
+    let mut base = vec![r.num(0); d]; // x reduced by the characteristic polynomial
+    if d == 1 {
+        base[0] = coeffs[0].clone();
+    } else {
+        base[1] = r.num(1);
+    }
+    let mut result = vec![r.num(0); d];
+    result[0] = r.num(1);
+
+    let mut exp = n;
+    while exp > 0 {
+        if exp & 1 > 0 {
+            result = mul_reduce(r, &result, &base, coeffs);
+        }
+        base = mul_reduce(r, &base, &base, coeffs);
+        exp >>= 1;
+    }
+    result.iter().zip(init).fold(r.num(0), |acc, (x, a)| r.add(&acc, &r.mul(x, a)))
+}
+
+/// Returns the n-th term of the linear recurrence modulo m
+pub fn nth_term_mod(coeffs: &[u64], init: &[u64], n: u64, m: u64) -> u64 {
+    if coeffs.is_empty() {
+        return 0;
+    }
+    let r = Modular(m);
+    let coeffs: Vec<u64> = coeffs.iter().map(|&c| c % m).collect();
+    let init: Vec<u64> = init.iter().map(|&a| a % m).collect();
+    kitamasa(&r, &coeffs, &init, n)
+}
+
+/// Returns the exact n-th term of the linear recurrence with non-negative coefficients.
+/// The terms grow exponentially, so this is only feasible for moderate n.
+pub fn nth_term(coeffs: &[u64], init: &[BigUint], n: u64) -> BigUint {
+    if coeffs.is_empty() {
+        return BigUint::from(0u8);
+    }
+    let coeffs: Vec<BigUint> = coeffs.iter().map(|&c| BigUint::from(c)).collect();
+    kitamasa(&Exact, &coeffs, init, n)
+}
+
+/// Returns the coefficients [c_1, ..., c_d] of the shortest linear recurrence generating
+/// the sequence modulo a prime p, by Berlekamp-Massey algorithm. At least 2d terms are
+/// needed to recover a recurrence of order d.
+pub fn berlekamp_massey(seq: &[u64], p: u64) -> Vec<u64> {
+    // connection polynomials C(x) = 1 + C_1 x + ... such that sum(C_j * a_(i-j)) = 0
+    let mut current = vec![1 % p];
+    let mut previous = vec![1 % p];
+    let mut length = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1;
+
+    for i in 0..seq.len() {
+        let discrepancy = (0..=length).fold(0, |acc, j| acc.add_mod(current[j].mul_mod(seq[i - j], &p), &p));
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        // C(x) -= d / b * x^shift * B(x)
+        let coef = discrepancy.mul_mod(last_discrepancy.inv_mod(&p).unwrap(), &p);
+        let backup = current.clone();
+        if current.len() < previous.len() + shift {
+            current.resize(previous.len() + shift, 0);
+        }
+        for (j, b) in previous.iter().enumerate() {
+            current[j + shift] = current[j + shift].sub_mod(coef.mul_mod(*b, &p), &p);
+        }
+
+        if 2 * length <= i {
+            length = i + 1 - length;
+            previous = backup;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+
+    current.resize(length + 1, 0);
+    current[1..].iter().map(|c| c.neg_mod(&p)).collect()
+}
+
+/// Guess the n-th term modulo a prime p from the initial terms of a sequence, assuming it
+/// satisfies a linear recurrence of order at most half of the given terms
+pub fn guess_nth_term(seq: &[u64], n: u64, p: u64) -> u64 {
+    let coeffs = berlekamp_massey(seq, p);
+    nth_term_mod(&coeffs, &seq[..coeffs.len()], n, p)
+}
+
+/// Returns a^n mod m for a square matrix a
+pub fn matrix_pow_mod(a: &[Vec<u64>], mut n: u64, m: u64) -> Vec<Vec<u64>> {
+    let size = a.len();
+    let mul = |x: &[Vec<u64>], y: &[Vec<u64>]| -> Vec<Vec<u64>> {
+        (0..size).map(|i| (0..size).map(|j| {
+            (0..size).fold(0, |acc, k| acc.add_mod(x[i][k].mul_mod(y[k][j], &m), &m))
+        }).collect()).collect()
+    };
+
+    let mut result: Vec<Vec<u64>> = (0..size).map(|i| (0..size).map(|j| (i == j) as u64 % m).collect()).collect();
+    let mut base = a.to_vec();
+    while n > 0 {
+        if n & 1 > 0 {
+            result = mul(&result, &base);
+        }
+        base = mul(&base, &base);
+        n >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{fibonacci, fibonacci_mod};
+
+    /// Generate the terms of a recurrence directly
+    fn terms(coeffs: &[u64], init: &[u64], count: usize, m: u64) -> Vec<u64> {
+        let mut seq = init.to_vec();
+        while seq.len() < count {
+            let i = seq.len();
+            let next = coeffs.iter().enumerate().fold(0, |acc, (j, c)| acc.add_mod(c.mul_mod(seq[i - j - 1], &m), &m));
+            seq.push(next);
+        }
+        seq
+    }
+
+    #[test]
+    fn nth_term_test() {
+        let p = 1000000007;
+        for (coeffs, init) in [(vec![1u64, 1], vec![0u64, 1]), (vec![2], vec![3]), (vec![3, 0, 5, 7], vec![1, 2, 3, 4])] {
+            let seq = terms(&coeffs, &init, 100, p);
+            for (n, &a) in seq.iter().enumerate() {
+                assert_eq!(nth_term_mod(&coeffs, &init, n as u64, p), a);
+            }
+            assert_eq!(berlekamp_massey(&seq, p), coeffs);
+            assert_eq!(guess_nth_term(&seq, 12345, p), nth_term_mod(&coeffs, &init, 12345, p));
+        }
+
+        let n = 1000000000000000;
+        assert_eq!(nth_term_mod(&[1, 1], &[0, 1], n, p), fibonacci_mod(n, p));
+        assert_eq!(nth_term(&[1, 1], &[BigUint::from(0u8), BigUint::from(1u8)], 1000), fibonacci(1000));
+
+        // a_i = 3a_(i-1) - a_(i-2) gives the even indexed Fibonacci numbers
+        let seq: Vec<u64> = (0..20).map(|i| fibonacci_mod(2 * i, p)).collect();
+        assert_eq!(berlekamp_massey(&seq, p), [3, p - 1]);
+        assert_eq!(berlekamp_massey(&[0; 10], p), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn matrix_test() {
+        let p = 1000000007;
+        let fib = matrix_pow_mod(&[vec![1, 1], vec![1, 0]], 1000, p);
+        assert_eq!(fib[0][1], fibonacci_mod(1000, p));
+        assert_eq!(matrix_pow_mod(&[vec![5, 1], vec![1, 0]], 0, p), [[1, 0], [0, 1]]);
+    }
+}
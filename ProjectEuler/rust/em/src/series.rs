@@ -0,0 +1,208 @@
+//! Truncated power series with exact coefficients
+//!
+//! A `Series` keeps the coefficients of x^0, ..., x^(n-1) for a fixed precision n. The
+//! coefficients can be unsigned (`u64`, `BigUint`) or signed (`i64`, `BigInt`) integers,
+//! the primitive types overflow as usual. For the modular series by NTT, see `poly`.
+
+use std::ops::{Add, Sub, Mul};
+use num_traits::{Zero, One};
+
+/// A power series truncated to a fixed number of terms
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Series<T> {
+    coeffs: Vec<T>
+}
+
+impl<T: Clone + Zero> Series<T> {
+    /// Create a series from the coefficients [a_0, a_1, ...] with the given precision,
+    /// which is the number of terms kept
+    pub fn new(mut coeffs: Vec<T>, precision: usize) -> Self {
+        coeffs.resize(precision, T::zero());
+        Series { coeffs }
+    }
+
+    /// Returns the zero series
+    pub fn zero(precision: usize) -> Self { Self::new(Vec::new(), precision) }
+
+    /// Returns the number of terms kept
+    #[inline]
+    pub fn precision(&self) -> usize { self.coeffs.len() }
+
+    /// Returns the coefficients [a_0, ..., a_(n-1)]
+    #[inline]
+    pub fn coeffs(&self) -> &[T] { &self.coeffs }
+
+    /// Returns the coefficient of x^k, which has to be within the precision
+    #[inline]
+    pub fn coeff(&self, k: usize) -> &T { &self.coeffs[k] }
+}
+
+impl<T: Clone + Zero + One> Series<T> {
+    /// Returns the series 1
+    pub fn one(precision: usize) -> Self { Self::monomial(T::one(), 0, precision) }
+
+    /// Returns c * x^k
+    pub fn monomial(c: T, k: usize, precision: usize) -> Self {
+        let mut result = Self::zero(precision);
+        if k < precision {
+            result.coeffs[k] = c;
+        }
+        result
+    }
+
+    /// Returns prod(1 / (1 - x^k)) for k in parts, whose coefficients count the partitions
+    /// into the given parts (each part can be used repeatedly)
+    pub fn euler_product(parts: &[u64], precision: usize) -> Self {
+        let mut result = Self::one(precision);
+        for &part in parts {
+            let part = part as usize;
+            assert!(part > 0);
+            for i in part..precision {
+                result.coeffs[i] = result.coeffs[i].clone() + result.coeffs[i - part].clone();
+            }
+        }
+        result
+    }
+
+    /// Returns prod(1 + x^k) for k in parts, whose coefficients count the partitions into
+    /// distinct given parts
+    pub fn distinct_product(parts: &[u64], precision: usize) -> Self {
+        let mut result = Self::one(precision);
+        for &part in parts {
+            let part = part as usize;
+            assert!(part > 0);
+            for i in (part..precision).rev() {
+                result.coeffs[i] = result.coeffs[i].clone() + result.coeffs[i - part].clone();
+            }
+        }
+        result
+    }
+
+    /// Returns 1 / (1 - a(x)) = 1 + a + a^2 + ..., the constant term of a has to be zero.
+    /// It counts the sequences of objects counted by a.
+    pub fn sequence(&self) -> Self {
+        let n = self.precision();
+        assert!(n == 0 || self.coeffs[0].is_zero());
+        let mut result = Self::zero(n);
+        for i in 0..n {
+            result.coeffs[i] = if i == 0 { T::one() } else {
+                (1..=i).fold(T::zero(), |acc, k| acc + self.coeffs[k].clone() * result.coeffs[i - k].clone())
+            };
+        }
+        result
+    }
+
+    /// Returns a(x)^exp by binary exponentiation
+    pub fn pow(&self, mut exp: u64) -> Self {
+        let mut result = Self::one(self.precision());
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 > 0 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+impl<T: Clone + PartialEq + Zero + One + Sub<Output = T>> Series<T> {
+    /// Returns 1 / a(x), the constant term of a has to be one. The inverse of 1 + c(x) with
+    /// non-zero c always has a negative coefficient, so this is meant for the signed types,
+    /// use `sequence` for the unsigned ones.
+    pub fn inverse(&self) -> Self {
+        let n = self.precision();
+        assert!(n == 0 || self.coeffs[0].is_one());
+        let mut result = Self::zero(n);
+        for i in 0..n {
+            result.coeffs[i] = if i == 0 { T::one() } else {
+                T::zero() - (1..=i).fold(T::zero(), |acc, k| acc + self.coeffs[k].clone() * result.coeffs[i - k].clone())
+            };
+        }
+        result
+    }
+}
+
+impl<T: Clone + Zero> Add for &Series<T> {
+    type Output = Series<T>;
+
+    fn add(self, rhs: &Series<T>) -> Series<T> {
+        let coeffs = self.coeffs.iter().zip(&rhs.coeffs).map(|(a, b)| a.clone() + b.clone()).collect();
+        Series { coeffs }
+    }
+}
+
+impl<T: Clone + Zero + Mul<Output = T>> Mul for &Series<T> {
+    type Output = Series<T>;
+
+    /// Truncated product, the precision is the smaller one of the two
+    fn mul(self, rhs: &Series<T>) -> Series<T> {
+        let n = self.precision().min(rhs.precision());
+        let mut result = Series::<T>::zero(n);
+        for (i, a) in self.coeffs.iter().take(n).enumerate() {
+            if a.is_zero() {
+                continue;
+            }
+            for (j, b) in rhs.coeffs.iter().take(n - i).enumerate() {
+                result.coeffs[i + j] = result.coeffs[i + j].clone() + a.clone() * b.clone();
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::{BigUint, BigInt};
+    use crate::fibonacci::fibonacci;
+    use crate::partitions::distinct_partitions;
+    use crate::prime::PrimeBuffer;
+
+    #[test]
+    fn product_test() {
+        // Project Euler 31, 76 and 77
+        let coins = Series::<u64>::euler_product(&[1, 2, 5, 10, 20, 50, 100, 200], 201);
+        assert_eq!(*coins.coeff(200), 73682);
+        let p = Series::<BigUint>::euler_product(&(1..100).collect::<Vec<_>>(), 101);
+        assert_eq!(*p.coeff(100), BigUint::from(190569291u64));
+        let mut pb = PrimeBuffer::new();
+        let p = Series::<u64>::euler_product(pb.primes(100), 101);
+        assert_eq!(p.coeffs().iter().position(|&x| x > 5000), Some(71));
+
+        let parts: Vec<u64> = (1..=100).collect();
+        let q = Series::<BigUint>::distinct_product(&parts, 101);
+        assert_eq!(q.coeffs(), &distinct_partitions(100)[..]);
+
+        // prod(1 - x^k) is the inverse of the partition generating function
+        let e = Series::<i64>::euler_product(&parts, 101);
+        let mut inv = Series::one(101);
+        for &k in &parts {
+            let mut factor = vec![0i64; k as usize + 1];
+            factor[0] = 1;
+            factor[k as usize] = -1;
+            inv = &inv * &Series::new(factor, 101);
+        }
+        assert_eq!(e.inverse(), inv);
+        assert_eq!(&e * &inv, Series::one(101));
+    }
+
+    #[test]
+    fn inverse_test() {
+        // 1 / (1 - x - x^2) generates the Fibonacci numbers
+        let f = Series::new(vec![BigInt::one(), -BigInt::one(), -BigInt::one()], 100).inverse();
+        let g = Series::new(vec![BigUint::zero(), BigUint::one(), BigUint::one()], 100).sequence();
+        for n in 0..100 {
+            assert_eq!(*g.coeff(n), fibonacci(n as u64 + 1));
+            assert_eq!(f.coeff(n).to_biguint().unwrap(), fibonacci(n as u64 + 1));
+        }
+
+        let binomial = Series::new(vec![1u64, 1], 8).pow(5);
+        assert_eq!(binomial.coeffs(), [1, 5, 10, 10, 5, 1, 0, 0]);
+        assert_eq!(&binomial + &Series::monomial(3, 7, 8), Series::new(vec![1, 5, 10, 10, 5, 1, 0, 3], 8));
+        assert_eq!(Series::<u64>::one(0).pow(3).precision(), 0);
+    }
+}
@@ -13,6 +13,23 @@ pub trait ModInt<Rhs = Self, Modulus = Self> {
 
     /// Return (self ^ exp) % m
     fn pow_mod(self, exp: Rhs, m: Modulus) -> Self::Output;
+
+    /// Return (self + rhs) % m
+    fn add_mod(self, rhs: Rhs, m: Modulus) -> Self::Output;
+
+    /// Return (self - rhs) % m, the result is always non-negative
+    fn sub_mod(self, rhs: Rhs, m: Modulus) -> Self::Output;
+}
+
+/// This trait describes modular arithmetic with a single operand
+pub trait ModUnary<Modulus = Self> {
+    type Output;
+
+    /// Return (-self) % m, the result is always non-negative
+    fn neg_mod(self, m: Modulus) -> Self::Output;
+
+    /// Return x such that (self * x) % m == 1, or None if self is not coprime to m
+    fn inv_mod(self, m: Modulus) -> Option<Self::Output>;
 }
 
 // wrapping simple functions
@@ -27,6 +44,10 @@ pub trait Arithmetic : Integer + NumOps {
 
     /// Generate a factor of the integer using Pollard's Rho algorithm
     fn pollard_rho(&self, offset: Self, trials: u32) -> Option<Self>;
+
+    /// Return the Jacobi symbol (self / n), n has to be odd. It equals the Legendre
+    /// symbol when n is a prime.
+    fn jacobi(&self, n: &Self) -> i8;
 }
 
 impl<T> Arithmetic for T
@@ -42,12 +63,12 @@ for<'r> &'r T: RefNum<T> + std::ops::Shr<usize, Output = T> + ModInt<&'r T, &'r
         let mut x = witness.pow_mod(&u, self);
         if x == T::one() || x == tm1 { return true }
 
-        for _ in 0..shift {
+        for _ in 1..shift {
             x = (&x).mul_mod(&x, self);
             if x == tm1 { return true }
         }
 
-        x == T::one()
+        false
     }
 
     fn pollard_rho(&self, offset: Self, trials: u32) -> Option<Self> {
@@ -59,7 +80,7 @@ for<'r> &'r T: RefNum<T> + std::ops::Shr<usize, Output = T> + ModInt<&'r T, &'r
             let mut i = 1; let mut j = 2;
             loop {
                 i += 1;
-                a = ((&a).mul_mod(&a, &self) + &offset) % self;
+                a = ((&a).mul_mod(&a, self) + &offset) % self;
                 if a == b {
                     trials -= 1;
                     continue 'trial_loop
@@ -77,4 +98,34 @@ for<'r> &'r T: RefNum<T> + std::ops::Shr<usize, Output = T> + ModInt<&'r T, &'r
         }
         None
     }
+
+    fn jacobi(&self, n: &Self) -> i8 {
+        assert!(n.is_odd());
+        let three = T::from_u8(3).unwrap();
+        let four = T::from_u8(4).unwrap();
+        let five = T::from_u8(5).unwrap();
+        let eight = T::from_u8(8).unwrap();
+
+        let mut a = self % n;
+        let mut n = n.clone();
+        let mut t = 1;
+        while !a.is_zero() {
+            // (2/n) = -1 iff n ≡ 3, 5 (mod 8)
+            let shift = a.trailing_zeros();
+            a = &a >> shift;
+            let n8 = &n % &eight;
+            if shift & 1 == 1 && (n8 == three || n8 == five) {
+                t = -t;
+            }
+
+            // quadratic reciprocity
+            if &a % &four == three && &n % &four == three {
+                t = -t;
+            }
+            std::mem::swap(&mut a, &mut n);
+            a = &a % &n;
+        }
+
+        if n.is_one() { t } else { 0 }
+    }
 }
@@ -7,7 +7,6 @@ use std::os::raw::{c_int, c_uchar};
 use std::string::ToString;
 use std::convert::TryFrom;
 
-use num_integer;
 use num_bigint::{BigInt, BigUint, ToBigInt, ToBigUint};
 use em::{int64, intbig, prime};
 use em::fraction as fraction;
@@ -118,6 +117,7 @@ type QuadraticSurdBig = fraction::QuadraticSurd<BigInt>;
 
 enum QuadraticSurdUnified {
     D64(QuadraticSurdInt64),
+    #[allow(dead_code)] // TODO: construct from big integers
     DBig(QuadraticSurdBig)
 }
 
@@ -176,6 +176,98 @@ impl PrimeBuffer {
         }
     }
 
+    fn primes_in_progression(&mut self, a: u64, q: u64, lo: u64, hi: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.primes_in_progression(a, q, lo, hi))
+    }
+
+    fn pi(&mut self, x: u64) -> PyResult<u64> {
+        Ok(self.data.pi(x))
+    }
+
+    fn pi_progression(&mut self, x: u64, q: u64, a: u64) -> PyResult<u64> {
+        Ok(self.data.pi_progression(x, q, a))
+    }
+
+    fn pi_residues(&mut self, x: u64, q: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.pi_residues(x, q))
+    }
+
+    fn factor_range(&mut self, lo: u64, hi: u64) -> PyResult<Vec<HashMap<u64, usize>>> {
+        Ok(self.data.factor_range(lo, hi))
+    }
+
+    fn mobius(&mut self, limit: u64) -> PyResult<Vec<i8>> {
+        Ok(self.data.mobius(limit))
+    }
+
+    fn count_squarefree(&mut self, n: u64) -> PyResult<u64> {
+        Ok(self.data.count_squarefree(n))
+    }
+
+    fn count_almost_primes(&mut self, n: u64, k: u32) -> PyResult<u64> {
+        Ok(self.data.count_almost_primes(n, k))
+    }
+
+    fn smooth_numbers(&mut self, bound: u64, limit: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.smooth_numbers(bound, limit).collect())
+    }
+
+    fn count_smooth(&mut self, bound: u64, limit: u64) -> PyResult<u64> {
+        Ok(self.data.count_smooth(bound, limit))
+    }
+
+    fn is_bsmooth(&mut self, target: BigUint, bound: u64) -> PyResult<bool> {
+        Ok(self.data.is_bsmooth(&target, bound))
+    }
+
+    fn poly_primes(&mut self, a: i64, b: i64, c: i64, lo: u64, hi: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.poly_primes(a, b, c, lo, hi))
+    }
+
+    fn poly_factors(&mut self, a: i64, b: i64, c: i64, lo: u64, hi: u64) -> PyResult<Vec<HashMap<u64, usize>>> {
+        Ok(self.data.poly_factors(a, b, c, lo, hi))
+    }
+
+    fn sqrt_mod(&mut self, a: u64, n: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.sqrt_mod(a, n).collect())
+    }
+
+    fn nth_root_mod(&mut self, a: u64, k: u64, p: u64) -> PyResult<Vec<u64>> {
+        Ok(self.data.nth_root_mod(a, k, p).collect())
+    }
+
+    fn carmichael_lambda(&mut self, n: u64) -> PyResult<u64> {
+        Ok(self.data.carmichael_lambda(n))
+    }
+
+    fn order(&mut self, a: u64, n: u64) -> PyResult<Option<u64>> {
+        Ok(self.data.order(a, n))
+    }
+
+    fn primitive_root(&mut self, n: u64) -> PyResult<Option<u64>> {
+        Ok(self.data.primitive_root(n))
+    }
+
+    fn discrete_log(&mut self, a: u64, b: u64, n: u64) -> PyResult<Option<(u64, u64)>> {
+        Ok(self.data.discrete_log(a, b, n).map(|log| (log.x0, log.period)))
+    }
+
+    fn binomial_mod(&mut self, n: u64, k: u64, m: u64) -> PyResult<u64> {
+        Ok(self.data.binomial_mod(n, k, m))
+    }
+
+    fn pisano_period(&mut self, m: u64) -> PyResult<Option<u64>> {
+        Ok(self.data.pisano_period(m))
+    }
+
+    fn prime_sum_mod(&mut self, n: u64, k: usize, m: u64) -> PyResult<u64> {
+        Ok(self.data.prime_sum_mod(n, k, m))
+    }
+
+    fn power_tower_mod(&mut self, a: u64, k: u64, m: u64) -> PyResult<u64> {
+        Ok(self.data.power_tower_mod(a, k, m))
+    }
+
     fn is_prime(&self, target: u64) -> PyResult<bool> {
         Ok(self.data.is_prime(target))
     }